@@ -1,54 +1,197 @@
 // App state and logic
 pub mod data;
+pub mod export;
+mod legacy;
 pub mod list;
+pub mod settings;
+pub mod stats;
+pub mod text;
 use crate::ui::draw;
 use crate::ui::events;
-use crossterm::{
-    event::{Event, KeyCode, KeyModifiers},
-    terminal::SetTitle,
-};
-use data::App;
+use chrono::Local;
+use crossterm::{event::Event, terminal::SetTitle};
+use data::{App, DataDeserialize, Error, Feedback, Journal};
+use notify_rust::Notification;
 use platform_dirs::AppDirs;
+use settings::Settings;
 use std::{
-    fs,
+    fmt, fs,
     io::{self, stdout},
+    path::PathBuf,
     time::{Duration, Instant},
 };
 use tui::{backend::Backend, Terminal};
 
 const TICK_RATE_MS: u64 = 25;
+const REMINDER_RATE_SECS: u64 = 30;
+
+/// Reasons a journal cannot be opened without user interaction
+pub enum OpenError {
+    Io(io::Error),
+    NotFound(String),
+    Unreadable(String, data::LoadError),
+    GotoNotFound(String, data::Error),
+}
+
+impl OpenError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Io(_) => 1,
+            Self::NotFound(_) => 2,
+            Self::Unreadable(_, _) => 3,
+            Self::GotoNotFound(_, _) => 4,
+        }
+    }
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::NotFound(name) => write!(f, "journal `{name}` not found"),
+            Self::Unreadable(name, e) => write!(f, "cannot open journal `{name}` ({e})"),
+            Self::GotoNotFound(path, e) => write!(f, "cannot go to `{path}` ({e})"),
+        }
+    }
+}
+
+/// Notify of newly due tasks, ignoring platforms without desktop notifications
+fn send_reminders(state: &mut App) {
+    if !state.settings.notifications || state.locked {
+        return;
+    }
+    let today = Local::now().date_naive();
+    for desc in state.journal.take_reminders(today) {
+        Notification::new()
+            .summary(&format!("Due: {}", state.journal.name))
+            .body(&desc)
+            .show()
+            .ok();
+    }
+}
+
+fn datadir() -> io::Result<PathBuf> {
+    let datadir = AppDirs::new(Some("devjournal"), false)
+        .ok_or_else(|| io::Error::other("failed to create user folder"))?
+        .data_dir;
+    fs::create_dir_all(&datadir)?;
+    Ok(datadir)
+}
+
+/// Check that a journal can be opened without prompting for a password, and that the path to go
+/// to resolves
+pub fn check_journal(name: &str, goto: Option<&str>) -> Result<(), OpenError> {
+    let filepath = datadir().map_err(OpenError::Io)?.join(name);
+    if !filepath.exists() {
+        return Err(OpenError::NotFound(name.to_owned()));
+    }
+    let journal = Journal::load_decrypt(&filepath, "")
+        .map_err(|e| OpenError::Unreadable(name.to_owned(), e))?;
+    if let Some(path) = goto {
+        journal
+            .resolve_path(path)
+            .map_err(|e| OpenError::GotoNotFound(path.to_owned(), e))?;
+    }
+    Ok(())
+}
 
 pub fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     target_name: Option<String>,
+    auto_lock_minutes: Option<u64>,
+    read_only: bool,
+    goto: Option<String>,
 ) -> io::Result<()> {
-    let datadir = AppDirs::new(Some("devjournal"), false)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "failed to create user folder"))?
-        .data_dir;
-    fs::create_dir_all(&datadir)?;
+    let datadir = datadir()?;
     let tick_rate = Duration::from_millis(TICK_RATE_MS);
     let mut app_state = App::new(datadir);
+    // Unreadable settings are kept for the user to fix, and not saved over
+    let mut save_settings = true;
+    match Settings::load(&app_state.datadir) {
+        Ok(mut settings) => {
+            for warning in settings.normalize() {
+                app_state.add_feedback(Feedback::warning(&format!("Settings: {warning}")));
+            }
+            app_state.settings = settings;
+        }
+        Err(e) => match Settings::back_up(&app_state.datadir) {
+            Ok(backup) => app_state.add_feedback(Error::from_cause(
+                &format!("Failed to load settings, moved to `{backup}`"),
+                e,
+            )),
+            Err(_) => {
+                save_settings = false;
+                app_state.add_feedback(Error::from_cause("Failed to load settings", e));
+            }
+        },
+    }
+    app_state.auto_lock_after = auto_lock_minutes.map(|minutes| Duration::from_secs(minutes * 60));
+    app_state.read_only = read_only;
+    app_state.goto = goto;
+    let target_name = target_name.or_else(|| app_state.settings.last_journal(&app_state.datadir));
     if let Some(name) = target_name {
         events::try_load_file(&mut app_state, name.as_str());
     }
     let mut last_tick = Instant::now();
+    let reminder_rate = Duration::from_secs(REMINDER_RATE_SECS);
+    let mut last_reminder = Instant::now()
+        .checked_sub(reminder_rate)
+        .unwrap_or_else(Instant::now);
     loop {
-        terminal.draw(|frame| draw(frame, &app_state, false))?;
+        terminal.draw(|frame| draw(frame, &app_state))?;
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
+        let settings = app_state.settings.clone();
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = crossterm::event::read()? {
-                if (KeyCode::Char('q'), KeyModifiers::CONTROL) == (key.code, key.modifiers) {
-                    return Ok(());
+            match crossterm::event::read()? {
+                Event::Key(key) => events::handle_event(key, &mut app_state),
+                Event::Paste(text) => events::handle_paste(&text, &mut app_state),
+                Event::Mouse(mouse) => {
+                    events::handle_mouse(mouse, &mut app_state, terminal.size()?)
                 }
-                events::handle_event(key, &mut app_state);
+                _ => (),
             }
         };
+        if !app_state.quit {
+            events::handle_tick(&mut app_state);
+        }
+        // Including changes by the tick, such as remembering an autosaved journal
+        if save_settings && app_state.settings != settings {
+            if let Err(e) = app_state.settings.save(&app_state.datadir) {
+                app_state.add_feedback(Error::from_cause("Failed to save settings", e));
+            }
+        }
+        if app_state.quit {
+            return Ok(());
+        }
         if last_tick.elapsed() >= tick_rate {
             let title = format!("Dev Journal - {}", app_state.journal.name);
             crossterm::queue!(stdout(), SetTitle(title))?;
             last_tick = Instant::now();
         }
+        if last_reminder.elapsed() >= reminder_rate {
+            send_reminders(&mut app_state);
+            last_reminder = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::LoadError;
+
+    #[test]
+    fn open_errors_have_distinct_exit_codes() {
+        let errors = [
+            OpenError::Io(io::Error::from(io::ErrorKind::PermissionDenied)),
+            OpenError::NotFound("journal".to_owned()),
+            OpenError::Unreadable("journal".to_owned(), LoadError::Decrypt),
+            OpenError::GotoNotFound("a/b".to_owned(), Error::from("no project `a`")),
+        ];
+        let codes: Vec<u8> = errors.iter().map(OpenError::exit_code).collect();
+        assert_eq!(codes, [1, 2, 3, 4]);
+        assert_eq!(errors[1].to_string(), "journal `journal` not found");
     }
 }
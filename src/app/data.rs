@@ -1,14 +1,45 @@
-use super::list::SelectionList;
-use crate::crypto::{decrypt, encrypt};
-use crate::ui::widgets::{files::FileListWidget, prompt::PromptWidget};
-use serde::{self, Deserialize, Serialize};
+use super::{
+    export::ExportFormat,
+    legacy::{JournalV0, ProjectV0},
+    list::SelectionList,
+    settings::Settings,
+    text::normalize_for_search,
+};
+use crate::crypto::{decrypt, encrypt, is_ciphertext, Algorithm, Password};
+use crate::ui::widgets::{
+    choice::ChoiceWidget, confirm::ConfirmWidget, files::FileListWidget, prompt::PromptWidget,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use regex::Regex;
+use serde::{self, de::DeserializeOwned, Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::ops::Add;
 use std::path::Path;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use std::{fmt, fs, path::PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
 
+/// Version of the saved data layout. Changing any saved field requires a new version, with the
+/// previous layout frozen in `legacy` for migrating older files.
+pub const FORMAT_VERSION: u8 = 1;
 pub const DEFAULT_WIDTH_PERCENT: u16 = 40;
+pub const NAME_MAX_LENGTH: usize = 28;
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+pub const TASK_MAX_DEPTH: u8 = 4;
+/// Named colors a task can be labelled with
+pub const TASK_COLORS: [&str; 6] = ["red", "yellow", "green", "cyan", "blue", "magenta"];
+pub const PRIORITY_LABELS: [&str; 4] = ["None", "Low", "Medium", "High"];
+/// Clearing more completed tasks than this asks for confirmation first
+pub const CLEAR_CONFIRM_THRESHOLD: usize = 3;
+/// Consecutive wrong passwords answered with a short delay, before requiring a longer pause
+pub const PASSWORD_ATTEMPTS: u32 = 5;
+pub const PASSWORD_LOCKOUT_SECS: u64 = 30;
+/// Digits not followed by a motion for this long select a project by number
+pub const COUNT_PREFIX_TIMEOUT_MS: u64 = 1000;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -62,6 +93,12 @@ impl From<Error> for String {
     }
 }
 
+impl<T> From<LoadError> for Result<T> {
+    fn from(value: LoadError) -> Result<T> {
+        Err(value.into())
+    }
+}
+
 impl<T> From<Error> for Result<T> {
     fn from(value: Error) -> Result<T> {
         Err(value)
@@ -86,13 +123,75 @@ impl From<Box<bincode::ErrorKind>> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self {
+            message: value.to_string(),
+            cause: Some(Box::new(Error::from(value.to_string()))),
+        }
+    }
+}
+
+impl From<arboard::Error> for Error {
+    fn from(value: arboard::Error) -> Self {
+        Self {
+            message: value.to_string(),
+            cause: Some(Box::new(Error::from(value.to_string()))),
+        }
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(value: csv::Error) -> Self {
+        Self {
+            message: value.to_string(),
+            cause: Some(Box::new(Error::from(value.to_string()))),
+        }
+    }
+}
+
+/// Reasons a saved file cannot be loaded, telling a wrong password apart from damaged or
+/// incompatible files
+#[derive(Debug)]
+pub enum LoadError {
+    Io(Error),
+    NotJournal,
+    /// Wrong password, or data altered since it was encrypted
+    Decrypt,
+    /// Written by a newer version of devjournal
+    Unsupported(String),
+    Corrupt(Error),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::NotJournal => write!(f, "not a devjournal file"),
+            Self::Decrypt => write!(f, "wrong password"),
+            Self::Unsupported(what) => write!(f, "unsupported {what}"),
+            Self::Corrupt(e) => write!(f, "not a valid devjournal file (cause: {e})"),
+        }
+    }
+}
+
+impl From<LoadError> for Error {
+    fn from(value: LoadError) -> Self {
+        match value {
+            LoadError::Io(e) => e,
+            LoadError::Corrupt(e) => Error::from_cause("not a valid devjournal file", e),
+            e => Error::from(e.to_string()),
+        }
+    }
+}
+
 pub trait DataSerialize<T>
 where
     Self: Serialize,
 {
-    fn save_encrypt(&self, filepath: &PathBuf, key: &str) -> Result<()> {
+    fn save_encrypt(&self, filepath: &PathBuf, key: &str, algorithm: Algorithm) -> Result<()> {
         let encoded = bincode::serialize(&self)?;
-        let encrypted = encrypt(&encoded, key)?;
+        let encrypted = encrypt(&encoded, key, algorithm, FORMAT_VERSION)?;
         fs::write(filepath, encrypted)?;
         Ok(())
     }
@@ -100,26 +199,123 @@ where
 
 pub trait DataDeserialize<T>
 where
-    T: for<'a> Deserialize<'a>,
+    T: DeserializeOwned,
 {
-    fn load_decrypt(filepath: &PathBuf, key: &str) -> Result<T> {
-        let encrypted = fs::read(filepath)?;
-        let decrypted = decrypt(&encrypted, key)?;
-        let decoded = bincode::deserialize::<T>(decrypted.as_slice())?;
+    /// Decode data saved in an earlier format version
+    fn migrate(version: u8, data: &[u8]) -> Result<T>;
+
+    fn load_decrypt(filepath: &PathBuf, key: &str) -> std::result::Result<T, LoadError> {
+        let encrypted = fs::read(filepath).map_err(|e| LoadError::Io(e.into()))?;
+        if !is_ciphertext(&encrypted) {
+            return Err(LoadError::NotJournal);
+        }
+        let (version, decrypted) = decrypt(&encrypted, key)?;
+        let decoded = match version.cmp(&FORMAT_VERSION) {
+            Ordering::Equal => bincode::deserialize::<T>(&decrypted).map_err(Error::from),
+            Ordering::Less => Self::migrate(version, &decrypted),
+            Ordering::Greater => {
+                return Err(LoadError::Unsupported(format!(
+                    "format version [{version}]"
+                )))
+            }
+        };
+        decoded.map_err(LoadError::Corrupt)
+    }
+
+    fn load_json(filepath: &PathBuf) -> Result<T> {
+        let text = fs::read_to_string(filepath)?;
+        let decoded = serde_json::from_str::<T>(&text)?;
         Ok(decoded)
     }
 }
 
-#[derive(Clone)]
 pub enum JournalPrompt {
     SetPassword,
+    /// Holds the password entered first, to be matched by the confirmation
+    ConfirmPassword(Password),
+    SetProjectPassword,
+    ConfirmProjectPassword(Password),
+    UnlockProject,
     RenameJournal,
     AddProject,
     RenameProject,
     AddSubProject,
     RenameSubProject,
     AddTask,
+    AddTasksBulk,
     RenameTask,
+    SetDueDate,
+    Filter,
+    SaveLayout,
+}
+
+impl JournalPrompt {
+    /// Maximum input length, for names that must fit in fixed-width areas
+    pub fn max_length(&self) -> Option<usize> {
+        match self {
+            Self::RenameJournal
+            | Self::AddProject
+            | Self::RenameProject
+            | Self::AddSubProject
+            | Self::RenameSubProject
+            | Self::SaveLayout => Some(NAME_MAX_LENGTH),
+            _ => None,
+        }
+    }
+
+    /// Input names or describes an item, see `normalize_name`
+    pub fn is_name(&self) -> bool {
+        self.max_length().is_some() || matches!(self, Self::AddTask | Self::RenameTask)
+    }
+
+    /// Accept multiple lines of input
+    pub fn multiline(&self) -> bool {
+        matches!(self, Self::AddTasksBulk)
+    }
+}
+
+/// What a choice from `App::choice` applies to
+#[derive(Clone, Copy)]
+pub enum ChoiceRequest {
+    /// Reload or overwrite the journal file changed on disk since it was loaded or saved
+    ChangedOnDisk,
+    Priority,
+    Color,
+    ExportFormat,
+    SortKey,
+    Url,
+    ProjectColor,
+}
+
+/// Which level of the journal horizontal navigation applies to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FocusRegion {
+    Tabs,
+    Subprojects,
+    Tasks,
+}
+
+impl FocusRegion {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Tabs => Self::Subprojects,
+            Self::Subprojects => Self::Tasks,
+            Self::Tasks => Self::Tabs,
+        }
+    }
+}
+
+/// What a yes answer from `App::confirm` goes ahead with
+#[derive(Clone, Copy)]
+pub enum ConfirmRequest {
+    DeleteProject,
+    DeleteSubProject,
+    /// Remove the completed tasks of the subproject
+    ClearCompleted,
+    /// Permanently delete the files in the trash
+    EmptyTrash,
+    /// Quit with unsaved changes
+    Quit,
 }
 
 #[derive(Clone, Copy)]
@@ -127,18 +323,35 @@ pub enum FileRequest {
     Save,
     Load,
     LoadMerge,
+    ExportProject,
+    ImportProject,
+    Duplicate { remove_completed: bool },
+    Template,
+    Export(ExportFormat),
+    ImportJson { merge: bool },
+    Checkpoint,
 }
 
 #[derive(Clone)]
 pub enum AppPrompt {
+    Unlock,
     NewJournal,
     LoadFile(String),
     MergeFile(String),
+    ImportProject(String),
+    RenameFile,
+    NewFromTemplate(String),
+    NewFromJson(String),
+    GoToProject,
 }
 
 pub enum FeedbackKind {
     Nominal,
+    /// Input that was not accepted, or an action that was skipped
+    Warning,
     Error,
+    /// Changes may be lost
+    Critical,
 }
 
 pub struct Feedback {
@@ -155,6 +368,20 @@ impl Feedback {
             instant: Instant::now(),
         }
     }
+
+    pub fn warning(message: &str) -> Self {
+        Self {
+            kind: FeedbackKind::Warning,
+            ..Self::new(message)
+        }
+    }
+
+    pub fn critical(error: Error) -> Self {
+        Self {
+            kind: FeedbackKind::Critical,
+            ..Self::new(&error.to_string())
+        }
+    }
 }
 
 impl From<String> for Feedback {
@@ -179,6 +406,23 @@ impl From<Error> for Feedback {
     }
 }
 
+/// Read-only views drawn over the journal
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    TaskInfo,
+    Stats,
+    Activity,
+    Today,
+}
+
+/// Indices of a task within the journal
+#[derive(Clone, Copy)]
+pub struct TaskLocation {
+    pub project: usize,
+    pub subproject: usize,
+    pub task: usize,
+}
+
 pub fn filename(filepath: &Path) -> String {
     filepath
         .file_name()
@@ -193,8 +437,47 @@ pub struct App<'a> {
     pub file_request: Option<FileRequest>,
     pub prompt: PromptWidget<'a>,
     pub prompt_request: Option<AppPrompt>,
+    pub choice: ChoiceWidget,
+    pub choice_request: Option<ChoiceRequest>,
+    pub confirm: ConfirmWidget,
+    pub confirm_request: Option<ConfirmRequest>,
     pub filepath: PathBuf,
+    /// Modification time of the journal file when last loaded or saved
+    pub file_modified: Option<SystemTime>,
     pub journal: Journal<'a>,
+    /// Digits typed for the next motion, or else a project number
+    pub count_prefix: Option<usize>,
+    pub focus: FocusRegion,
+    pub overlay: Option<Overlay>,
+    pub settings: Settings,
+    pub last_input: Instant,
+    pub locked: bool,
+    /// Ignore keys that would change the journal
+    pub read_only: bool,
+    /// Lock after this long without input, overriding the settings for this session only
+    pub auto_lock_after: Option<Duration>,
+    /// `Journal::fingerprint` when last loaded or saved
+    pub saved_fingerprint: u64,
+    /// Exit at the end of the current event
+    pub quit: bool,
+    /// Subproject and task indices of a task being dragged with the mouse
+    pub drag: Option<(usize, usize)>,
+    /// `last_input` when autosave last ran, to run once per pause in input
+    pub autosave_input: Option<Instant>,
+    /// Already asked to set a password so that autosave may run
+    pub autosave_prompted: bool,
+    /// Show the color debug view instead of the project
+    pub debug: bool,
+    /// Tasks listed in the today overlay
+    pub today: SelectionList<TaskLocation>,
+    /// Consecutive wrong passwords when loading or unlocking
+    pub password_failures: u32,
+    /// No password attempts are accepted until then
+    pub password_retry_at: Option<Instant>,
+    /// Path to select once the journal is loaded, see `Journal::select_path`
+    pub goto: Option<String>,
+    /// Opened on first copy and kept, as some platforms lose the contents when it is closed
+    pub clipboard: Option<arboard::Clipboard>,
 }
 
 impl<'a> App<'a> {
@@ -206,16 +489,64 @@ impl<'a> App<'a> {
             file_request: None,
             prompt: PromptWidget::default(),
             prompt_request: None,
+            choice: ChoiceWidget::default(),
+            choice_request: None,
+            confirm: ConfirmWidget::default(),
+            confirm_request: None,
             filepath: datadir.join("new_journal"),
+            file_modified: None,
             journal: Default::default(),
+            count_prefix: None,
+            focus: FocusRegion::Tasks,
+            overlay: None,
+            settings: Settings::default(),
+            last_input: Instant::now(),
+            locked: false,
+            read_only: false,
+            auto_lock_after: None,
+            saved_fingerprint: Journal::default().fingerprint(),
+            quit: false,
+            drag: None,
+            autosave_input: None,
+            autosave_prompted: false,
+            debug: false,
+            today: SelectionList::default(),
+            password_failures: 0,
+            password_retry_at: None,
+            goto: None,
+            clipboard: None,
         }
     }
 
+    /// Time left before another password attempt is accepted
+    pub fn password_wait(&self) -> Option<Duration> {
+        self.password_retry_at
+            .and_then(|retry_at| retry_at.checked_duration_since(Instant::now()))
+            .filter(|wait| !wait.is_zero())
+    }
+
+    pub fn password_failed(&mut self) {
+        self.password_failures += 1;
+        self.password_retry_at = Some(Instant::now() + password_backoff(self.password_failures));
+    }
+
+    pub fn password_succeeded(&mut self) {
+        self.password_failures = 0;
+        self.password_retry_at = None;
+    }
+
+    /// Whether the journal changed since it was last loaded or saved
+    pub fn is_dirty(&self) -> bool {
+        self.journal.fingerprint() != self.saved_fingerprint
+    }
+
     pub fn feedback(&self) -> Option<&Feedback> {
-        if let Some(feedback) = self.feedback_stack.get(0) {
+        if let Some(feedback) = self.feedback_stack.first() {
             let show_duration = match feedback.kind {
                 FeedbackKind::Nominal => 1250,
+                FeedbackKind::Warning => 3000,
                 FeedbackKind::Error => 5000,
+                FeedbackKind::Critical => 10000,
             };
             if Instant::now() - feedback.instant <= Duration::from_millis(show_duration) {
                 return Some(feedback);
@@ -224,6 +555,22 @@ impl<'a> App<'a> {
         None
     }
 
+    /// Whether changes should be saved automatically for lack of input
+    pub fn is_autosave_due(&self) -> bool {
+        self.autosave_input != Some(self.last_input)
+            && self
+                .settings
+                .autosave_after()
+                .is_some_and(|delay| self.last_input.elapsed() >= delay)
+    }
+
+    /// Whether the journal should be locked for lack of input
+    pub fn is_inactive(&self) -> bool {
+        self.auto_lock_after
+            .or_else(|| self.settings.auto_lock_after())
+            .is_some_and(|timeout| self.last_input.elapsed() >= timeout)
+    }
+
     pub fn add_feedback<F>(&mut self, feedback: F)
     where
         F: Into<Feedback>,
@@ -235,8 +582,10 @@ impl<'a> App<'a> {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Journal<'a> {
     pub name: String,
-    pub password: String,
+    pub password: Password,
     pub projects: SelectionList<Project<'a>>,
+    /// Keep projects sorted by name instead of in the order they were added
+    pub alphabetical_projects: bool,
 }
 
 impl<'a> Journal<'a> {
@@ -250,6 +599,211 @@ impl<'a> Journal<'a> {
     pub fn project(&mut self) -> Option<&mut Project<'a>> {
         self.projects.get_item_mut(None)
     }
+
+    /// Sort projects by name if `alphabetical_projects`, keeping the selected project selected
+    pub fn sort_projects(&mut self) {
+        if self.alphabetical_projects {
+            self.projects.sort_by(|a, b| {
+                normalize_for_search(&a.name)
+                    .cmp(&normalize_for_search(&b.name))
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+        }
+    }
+
+    /// Index of the project whose name best matches the query, see `match_rank`
+    pub fn find_project(&self, query: &str) -> Option<usize> {
+        self.projects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, p)| match_rank(query, &p.name).map(|rank| (rank, index)))
+            .min()
+            .map(|(_, index)| index)
+    }
+
+    /// The name, with a numbered suffix if a project by that name already exists
+    pub fn unique_project_name(&self, name: &str) -> String {
+        let taken = |candidate: &str| self.projects.iter().any(|p| p.name == candidate);
+        let mut candidate = name.to_owned();
+        let mut suffix = 2;
+        while taken(&candidate) {
+            candidate = format!("{name} ({suffix})");
+            suffix += 1;
+        }
+        candidate
+    }
+
+    /// Hash of the journal content, ignoring selections and layout
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (&self.name, &self.password, &self.alphabetical_projects).hash(&mut hasher);
+        self.projects.iter().for_each(|p| p.name.hash(&mut hasher));
+        // Summed so that a change to one project (e.g. unlocking) can be accounted for on its own
+        self.projects
+            .iter()
+            .fold(hasher.finish(), |sum, p| sum.wrapping_add(p.fingerprint()))
+    }
+
+    /// A copy of the journal with each password-protected project sealed
+    pub fn sealed(&self, algorithm: Algorithm) -> Result<Self> {
+        let mut sealed = self.clone();
+        for index in 0..sealed.projects.len() {
+            if let Some(project) = sealed.projects.get_item_mut(Some(index)) {
+                *project = project.sealed(algorithm)?;
+            }
+        }
+        Ok(sealed)
+    }
+
+    /// Apply to each subproject of all unlocked projects
+    fn for_each_subproject(&mut self, mut f: impl FnMut(&mut SubProject)) {
+        for project_index in 0..self.projects.len() {
+            if let Some(project) = self.projects.get_item_mut(Some(project_index)) {
+                for index in 0..project.subprojects.len() {
+                    if let Some(subproject) = project.subprojects.get_item_mut(Some(index)) {
+                        f(subproject);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Descriptions of tasks due by the given day not yet reminded of, marking them reminded
+    pub fn take_reminders(&mut self, day: NaiveDate) -> Vec<String> {
+        let mut reminders = Vec::new();
+        self.for_each_subproject(|subproject| {
+            for task in subproject.tasks.iter_mut() {
+                if task.needs_reminder(day) {
+                    task.reminded = true;
+                    reminders.push(task.desc.clone());
+                }
+            }
+        });
+        reminders
+    }
+
+    pub fn remove_completed(&mut self) {
+        self.for_each_subproject(|subproject| {
+            subproject.tasks.retain(|task| !task.is_completed());
+        });
+    }
+
+    pub fn reset_completion(&mut self) {
+        self.for_each_subproject(|subproject| {
+            for index in 0..subproject.tasks.len() {
+                if let Some(task) = subproject.tasks.get_item_mut(Some(index)) {
+                    task.completed_at = None;
+                }
+            }
+        });
+    }
+
+    /// Open tasks due on or before the given day, soonest due first
+    pub fn due_tasks(&self, day: NaiveDate) -> Vec<TaskLocation> {
+        let mut due = Vec::new();
+        for (project_index, project) in self.projects.iter().enumerate() {
+            for (subproject_index, subproject) in project.subprojects.iter().enumerate() {
+                for (task_index, task) in subproject.tasks.iter().enumerate() {
+                    if task.is_due_by(day) {
+                        due.push(TaskLocation {
+                            project: project_index,
+                            subproject: subproject_index,
+                            task: task_index,
+                        });
+                    }
+                }
+            }
+        }
+        due.sort_by_key(|location| self.task_at(*location).map(|(_, _, task)| task.due_date()));
+        due
+    }
+
+    pub fn task_at(&self, location: TaskLocation) -> Option<(&Project<'a>, &SubProject, &Task)> {
+        let project = self.projects.get_item(Some(location.project))?;
+        let subproject = project.subprojects.get_item(Some(location.subproject))?;
+        let task = subproject.tasks.get_item(Some(location.task))?;
+        Some((project, subproject, task))
+    }
+
+    /// Select the project, subproject and task at the location
+    pub fn select_location(&mut self, location: TaskLocation) -> Result<()> {
+        self.projects.select(location.project)?;
+        let project = self.project().ok_or(Error::from("no project selected"))?;
+        project.subprojects.select(location.subproject)?;
+        let subproject = project
+            .subproject()
+            .ok_or(Error::from("no subproject selected"))?;
+        subproject.tasks.select(location.task)
+    }
+
+    /// Indices of the project, subproject and task named by a `project/subproject/task` path,
+    /// trailing segments may be omitted
+    pub fn resolve_path(&self, path: &str) -> Result<Vec<usize>> {
+        let segments: Vec<&str> = path.split('/').collect();
+        if segments.len() > 3 {
+            return Error::from(format!("too many segments in `{path}`")).into();
+        }
+        let names = self.projects.iter().map(|p| p.name.as_str());
+        let project_index = find_named(names, segments[0])
+            .ok_or_else(|| Error::from(format!("project `{}` not found", segments[0])))?;
+        let project = self
+            .projects
+            .get_item(Some(project_index))
+            .expect("index was found, should be in range");
+        let mut indices = vec![project_index];
+        let Some(name) = segments.get(1) else {
+            return Ok(indices);
+        };
+        if project.is_locked() {
+            return Error::from(format!("project `{}` is locked", project.name)).into();
+        }
+        let names = project.subprojects.iter().map(|s| s.name.as_str());
+        let subproject_index = find_named(names, name).ok_or_else(|| {
+            Error::from(format!(
+                "subproject `{name}` not found in `{}`",
+                project.name
+            ))
+        })?;
+        indices.push(subproject_index);
+        let Some(desc) = segments.get(2) else {
+            return Ok(indices);
+        };
+        let subproject = project
+            .subprojects
+            .get_item(Some(subproject_index))
+            .expect("index was found, should be in range");
+        let descs = subproject
+            .tasks
+            .iter()
+            .map(|t| t.desc.lines().next().unwrap_or_default());
+        let task_index = find_named(descs, desc).ok_or_else(|| {
+            Error::from(format!("task `{desc}` not found in `{}`", subproject.name))
+        })?;
+        indices.push(task_index);
+        Ok(indices)
+    }
+
+    /// Select the items named by a path (see `resolve_path`), selecting nothing if it does not
+    /// resolve
+    pub fn select_path(&mut self, path: &str) -> Result<()> {
+        let indices = self.resolve_path(path)?;
+        self.projects.select(indices[0])?;
+        let project = self.project().ok_or(Error::from("no project selected"))?;
+        if let Some(index) = indices.get(1) {
+            project.subprojects.select(*index)?;
+        }
+        if let (Some(index), Some(subproject)) = (indices.get(2), project.subproject()) {
+            subproject.tasks.select(*index)?;
+        }
+        Ok(())
+    }
+
+    pub fn selected_task(&self) -> Option<&Task> {
+        self.projects
+            .selected()
+            .and_then(|p| p.subprojects.selected())
+            .and_then(|s| s.tasks.selected())
+    }
 }
 
 impl<'a> Default for Journal<'a> {
@@ -258,15 +812,23 @@ impl<'a> Default for Journal<'a> {
         projects.select_next();
         Journal {
             name: "New Journal".to_owned(),
-            password: "".to_owned(),
+            password: Password::default(),
             projects,
+            alphabetical_projects: false,
         }
     }
 }
 
 impl<'a> DataSerialize<Journal<'a>> for Journal<'a> {}
 
-impl<'a> DataDeserialize<Journal<'a>> for Journal<'a> {}
+impl<'a> DataDeserialize<Journal<'a>> for Journal<'a> {
+    fn migrate(version: u8, data: &[u8]) -> Result<Self> {
+        match version {
+            0 => Ok(bincode::deserialize::<JournalV0>(data)?.into()),
+            _ => Error::from(format!("unsupported format version [{version}]")).into(),
+        }
+    }
+}
 
 impl<'a> From<Project<'a>> for Journal<'a> {
     fn from(project: Project<'a>) -> Self {
@@ -274,6 +836,7 @@ impl<'a> From<Project<'a>> for Journal<'a> {
             name: project.name.clone(),
             password: project.password.clone(),
             projects: SelectionList::from(vec![project]),
+            alphabetical_projects: false,
         }
     }
 }
@@ -282,18 +845,21 @@ impl<'a> Add<Journal<'a>> for Journal<'a> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
+        let mut journal = Self {
             name: self.name,
             password: self.password,
             projects: self.projects + rhs.projects,
-        }
+            alphabetical_projects: self.alphabetical_projects,
+        };
+        journal.sort_projects();
+        journal
     }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Project<'a> {
     pub name: String,
-    pub password: String,
+    pub password: Password,
     pub subprojects: SelectionList<SubProject>,
     #[serde(skip)]
     pub prompt: PromptWidget<'a>,
@@ -301,6 +867,16 @@ pub struct Project<'a> {
     pub prompt_request: Option<JournalPrompt>,
     pub focused_width_percent: u16,
     pub split_vertical: bool,
+    pub hide_empty: bool,
+    /// Show the age of each task at the end of its row
+    pub show_ages: bool,
+    /// Subprojects encrypted with the project password, until unlocked
+    pub locked: Option<Vec<u8>>,
+    #[serde(skip)]
+    pub filter: Option<TaskFilter>,
+    /// Color of the tab label, one of `TASK_COLORS`
+    pub color: Option<String>,
+    pub layouts: Vec<LayoutPreset>,
 }
 
 impl<'a> Project<'a> {
@@ -314,6 +890,133 @@ impl<'a> Project<'a> {
     pub fn subproject(&mut self) -> Option<&mut SubProject> {
         self.subprojects.get_item_mut(None)
     }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_some()
+    }
+
+    /// Save the current layout as a preset, replacing any preset of the same name
+    pub fn save_layout(&mut self, name: &str) {
+        let preset = LayoutPreset {
+            name: name.to_owned(),
+            split_vertical: self.split_vertical,
+            focused_width_percent: self.focused_width_percent,
+            subproject: self.subprojects.selection(),
+        };
+        match self.layouts.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.layouts.push(preset),
+        }
+    }
+
+    /// Apply the preset after the one matching the current layout (or the first), returning its
+    /// name
+    pub fn cycle_layout(&mut self) -> Option<String> {
+        let next = self
+            .layouts
+            .iter()
+            .position(|preset| preset.is_applied(self))
+            .map_or(0, |i| (i + 1) % self.layouts.len());
+        let preset = self.layouts.get(next)?.clone();
+        self.split_vertical = preset.split_vertical;
+        self.focused_width_percent = preset.focused_width_percent;
+        if let Some(index) = preset.subproject {
+            self.subprojects.select(index).ok();
+        }
+        Some(preset.name)
+    }
+
+    /// Select the next (or previous) task matching the filter, across subprojects and wrapping
+    /// around, returning whether there was a match
+    pub fn select_match(&mut self, backwards: bool) -> bool {
+        let Some(filter) = &self.filter else {
+            return false;
+        };
+        let mut matches = vec![];
+        for (s, subproject) in self.subprojects.iter().enumerate() {
+            for (t, task) in subproject.tasks.iter().enumerate() {
+                if filter.matches(task) {
+                    matches.push((s, Some(t)));
+                }
+            }
+        }
+        let current = self.subprojects.selection().map(|s| {
+            (
+                s,
+                self.subprojects
+                    .selected()
+                    .and_then(|s| s.tasks.selection()),
+            )
+        });
+        let found = match (backwards, current) {
+            (false, Some(current)) => matches.iter().find(|m| **m > current),
+            (true, Some(current)) => matches.iter().rev().find(|m| **m < current),
+            _ => None,
+        };
+        let found = match backwards {
+            false => found.or(matches.first()),
+            true => found.or(matches.last()),
+        };
+        let Some(&(s, Some(t))) = found else {
+            return false;
+        };
+        self.subprojects.select(s).ok();
+        if let Some(subproject) = self.subproject() {
+            subproject.tasks.select(t).ok();
+        }
+        true
+    }
+
+    /// Hash of the project content, see `Journal::fingerprint`
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (
+            &self.name,
+            &self.password,
+            &self.locked,
+            &self.color,
+            &self.layouts,
+        )
+            .hash(&mut hasher);
+        for subproject in self.subprojects.iter() {
+            (&subproject.name, &subproject.sort_key).hash(&mut hasher);
+            subproject
+                .tasks
+                .iter()
+                .for_each(|task| task.hash(&mut hasher));
+        }
+        hasher.finish()
+    }
+
+    pub fn unlock(&mut self, key: &str) -> std::result::Result<(), LoadError> {
+        if let Some(encrypted) = &self.locked {
+            let (version, decrypted) = decrypt(encrypted, key)?;
+            // Locked projects were introduced in version 1
+            if version != FORMAT_VERSION {
+                return Err(LoadError::Unsupported(format!(
+                    "format version [{version}]"
+                )));
+            }
+            self.subprojects = bincode::deserialize(decrypted.as_slice())
+                .map_err(|e| LoadError::Corrupt(e.into()))?;
+            self.password = Password::from(key);
+            self.locked = None;
+        };
+        Ok(())
+    }
+
+    /// A copy of the project with its subprojects encrypted by the project password, if set
+    pub fn sealed(&self, algorithm: Algorithm) -> Result<Self> {
+        let mut sealed = self.clone();
+        if !self.password.is_empty() && !self.is_locked() {
+            let encoded = bincode::serialize(&self.subprojects)?;
+            let encrypted = encrypt(&encoded, &self.password, algorithm, FORMAT_VERSION)?;
+            sealed.locked = Some(encrypted);
+            sealed.subprojects = SelectionList::default();
+            sealed.password = Password::default();
+        };
+        Ok(sealed)
+    }
 }
 
 impl<'a> Clone for Project<'a> {
@@ -324,6 +1027,11 @@ impl<'a> Clone for Project<'a> {
             subprojects: self.subprojects.clone(),
             split_vertical: self.split_vertical,
             focused_width_percent: self.focused_width_percent,
+            hide_empty: self.hide_empty,
+            show_ages: self.show_ages,
+            locked: self.locked.clone(),
+            color: self.color.clone(),
+            layouts: self.layouts.clone(),
             ..Default::default()
         }
     }
@@ -333,12 +1041,18 @@ impl<'a> Default for Project<'a> {
     fn default() -> Self {
         Self {
             name: "New Project".to_owned(),
-            password: "".to_owned(),
+            password: Password::default(),
             subprojects: SelectionList::from(vec![SubProject::default()]),
             prompt: PromptWidget::default().width_hint(0.7),
             prompt_request: None,
             focused_width_percent: DEFAULT_WIDTH_PERCENT,
             split_vertical: false,
+            hide_empty: false,
+            show_ages: false,
+            locked: None,
+            filter: None,
+            color: None,
+            layouts: Vec::new(),
         }
     }
 }
@@ -353,6 +1067,11 @@ impl<'a> Add<Project<'a>> for Project<'a> {
             subprojects: self.subprojects + rhs.subprojects,
             split_vertical: self.split_vertical,
             focused_width_percent: self.focused_width_percent,
+            hide_empty: self.hide_empty,
+            show_ages: self.show_ages,
+            locked: self.locked.clone(),
+            color: self.color.clone(),
+            layouts: self.layouts.clone(),
             ..Default::default()
         }
     }
@@ -360,12 +1079,110 @@ impl<'a> Add<Project<'a>> for Project<'a> {
 
 impl<'a> DataSerialize<Project<'a>> for Project<'a> {}
 
-impl<'a> DataDeserialize<Project<'a>> for Project<'a> {}
+impl<'a> DataDeserialize<Project<'a>> for Project<'a> {
+    fn migrate(version: u8, data: &[u8]) -> Result<Self> {
+        match version {
+            0 => Ok(bincode::deserialize::<ProjectV0>(data)?.into()),
+            _ => Error::from(format!("unsupported format version [{version}]")).into(),
+        }
+    }
+}
+
+/// Arrangement of a project's subprojects, saved by name
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct LayoutPreset {
+    pub name: String,
+    pub split_vertical: bool,
+    pub focused_width_percent: u16,
+    /// Index of the focused subproject
+    pub subproject: Option<usize>,
+}
+
+impl LayoutPreset {
+    fn is_applied(&self, project: &Project) -> bool {
+        self.split_vertical == project.split_vertical
+            && self.focused_width_percent == project.focused_width_percent
+            && (self.subproject.is_none() || self.subproject == project.subprojects.selection())
+    }
+}
+
+/// Matches task descriptions, by regex with a `re:` prefix or else by substring ignoring case and
+/// accents
+#[derive(Clone)]
+pub enum TaskFilter {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl TaskFilter {
+    pub fn new(pattern: &str) -> Result<Self> {
+        match pattern.strip_prefix("re:") {
+            Some(regex) => Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|e| Error::from(e.to_string())),
+            None => Ok(Self::Substring(normalize_for_search(pattern))),
+        }
+    }
+
+    pub fn pattern(&self) -> String {
+        match self {
+            Self::Substring(text) => text.clone(),
+            Self::Regex(regex) => format!("re:{}", regex.as_str()),
+        }
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Self::Substring(text) => normalize_for_search(&task.desc).contains(text),
+            Self::Regex(regex) => regex.is_match(&task.desc),
+        }
+    }
+}
+
+/// Order tasks of a subproject are kept in automatically
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskSortKey {
+    Created,
+    Due,
+    Priority,
+    Completion,
+}
+
+impl TaskSortKey {
+    pub const ALL: [Self; 4] = [Self::Created, Self::Due, Self::Priority, Self::Completion];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Due => "due",
+            Self::Priority => "priority",
+            Self::Completion => "completion",
+        }
+    }
+
+    /// Oldest, soonest due, highest priority, or open tasks first
+    pub fn compare(&self, a: &Task, b: &Task) -> Ordering {
+        match self {
+            Self::Created => a.created_at.cmp(&b.created_at),
+            Self::Due => match (&a.due_at, &b.due_at) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            Self::Priority => b.priority.cmp(&a.priority),
+            Self::Completion => match (&a.completed_at, &b.completed_at) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            },
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SubProject {
     pub name: String,
     pub tasks: SelectionList<Task>,
+    /// Keep tasks sorted after each change, cleared by manual reordering
+    pub sort_key: Option<TaskSortKey>,
 }
 
 impl Default for SubProject {
@@ -373,6 +1190,7 @@ impl Default for SubProject {
         Self {
             name: "Tasks".to_owned(),
             tasks: SelectionList::default(),
+            sort_key: None,
         }
     }
 }
@@ -382,12 +1200,137 @@ impl SubProject {
         Self {
             name: name.to_owned(),
             tasks: SelectionList::default(),
+            sort_key: None,
         }
     }
 
+    /// Move pinned tasks in front of the rest, keeping their order unless sorting by the
+    /// auto-sort key. Call after every change to the order of tasks.
+    pub fn sort_tasks(&mut self) {
+        let key = self.sort_key;
+        self.tasks.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| key.map_or(Ordering::Equal, |key| key.compare(a, b)))
+        });
+    }
+
     pub fn task(&mut self) -> Option<&mut Task> {
         self.tasks.get_item_mut(None)
     }
+
+    pub fn completed_count(&self) -> usize {
+        self.tasks.iter().filter(|task| task.is_completed()).count()
+    }
+
+    /// Remove completed tasks, returning how many were removed
+    pub fn clear_completed(&mut self) -> usize {
+        self.tasks.retain(|task| !task.is_completed()).len()
+    }
+
+    /// Complete (or reopen) every task, returning how many changed
+    pub fn set_all_completed(&mut self, completed: bool) -> usize {
+        let completed_at = completed.then(timestamp_now);
+        let mut count = 0;
+        for task in self.tasks.iter_mut() {
+            if task.is_completed() != completed {
+                task.completed_at = completed_at.clone();
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Nest the selected task under the task above it
+    pub fn indent_task(&mut self) {
+        let Some(index) = self.tasks.selection() else {
+            return;
+        };
+        let Some(max_depth) = index
+            .checked_sub(1)
+            .and_then(|prev| self.tasks.get_item(Some(prev)))
+            .map(|prev| (prev.depth + 1).min(TASK_MAX_DEPTH))
+        else {
+            return;
+        };
+        if let Some(task) = self.task() {
+            task.depth = (task.depth + 1).min(max_depth);
+        }
+    }
+
+    pub fn outdent_task(&mut self) {
+        if let Some(task) = self.task() {
+            task.depth = task.depth.saturating_sub(1);
+        }
+    }
+
+    /// Toggle pinning of the selected task, moving it to the end of the pinned tasks (or just
+    /// after them when unpinned)
+    pub fn toggle_task_pinned(&mut self) {
+        let Some(task) = self.task() else {
+            return;
+        };
+        task.pinned = !task.pinned;
+        let pinned = task.pinned;
+        let pinned_count = self.tasks.iter().filter(|task| task.pinned).count();
+        let target = match pinned {
+            true => pinned_count - 1,
+            false => pinned_count,
+        };
+        self.tasks.move_to(target).ok();
+        self.sort_tasks();
+    }
+
+    pub fn marked(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.tasks.iter_mut().filter(|task| task.marked)
+    }
+
+    /// Remove the marked tasks, returning them in order and unmarked
+    pub fn pop_marked(&mut self) -> Vec<Task> {
+        let mut tasks = self.tasks.retain(|task| !task.marked);
+        tasks.iter_mut().for_each(|task| task.marked = false);
+        tasks
+    }
+
+    /// Complete all marked tasks, or reopen them if most are completed, returning
+    /// whether they were completed and how many
+    pub fn toggle_marked_completed(&mut self) -> (bool, usize) {
+        let (completed, total) = self.marked().fold((0, 0), |(c, t), task| {
+            (c + task.is_completed() as usize, t + 1)
+        });
+        let complete = completed * 2 <= total;
+        for task in self.marked() {
+            if task.is_completed() != complete {
+                task.toggle_completed();
+            }
+        }
+        (complete, total)
+    }
+
+    /// Toggle completion of the selected task, completing its subtasks with it
+    pub fn toggle_task_completed(&mut self) {
+        let Some(index) = self.tasks.selection() else {
+            return;
+        };
+        let Some(task) = self.task() else {
+            return;
+        };
+        task.toggle_completed();
+        let (depth, completed_at) = (task.depth, task.completed_at.clone());
+        if completed_at.is_none() {
+            return;
+        }
+        let mut child_index = index + 1;
+        while let Some(child) = self.tasks.get_item_mut(Some(child_index)) {
+            if child.depth <= depth {
+                break;
+            }
+            if !child.is_completed() {
+                child.completed_at = completed_at.clone();
+            }
+            child_index += 1;
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -395,20 +1338,716 @@ pub struct Task {
     pub desc: String,
     pub created_at: String,
     pub completed_at: Option<String>,
+    /// Nesting level under the preceding tasks
+    pub depth: u8,
+    /// Kept at the top of the subproject
+    pub pinned: bool,
+    /// Color label, one of `TASK_COLORS`
+    pub color: Option<String>,
+    /// Index into `PRIORITY_LABELS`
+    pub priority: u8,
+    /// Date the task is due, in `DATE_FORMAT`
+    pub due_at: Option<String>,
+    /// Included in bulk operations, for this session only
+    #[serde(skip)]
+    pub marked: bool,
+    /// A reminder was sent for the due date, for this session only
+    #[serde(skip)]
+    pub reminded: bool,
+}
+
+impl Hash for Task {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (
+            &self.desc,
+            &self.created_at,
+            &self.completed_at,
+            self.depth,
+            self.pinned,
+            &self.color,
+            self.priority,
+            &self.due_at,
+        )
+            .hash(state);
+    }
 }
 
 impl Task {
     pub fn new(desc: &str) -> Self {
         Self {
             desc: desc.to_owned(),
-            created_at: "2020-02-02 12:00:00".to_owned(),
+            created_at: timestamp_now(),
             completed_at: None,
+            depth: 0,
+            pinned: false,
+            color: None,
+            priority: 0,
+            due_at: None,
+            marked: false,
+            reminded: false,
         }
     }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        self.due_at
+            .as_ref()
+            .and_then(|due| NaiveDate::parse_from_str(due, DATE_FORMAT).ok())
+    }
+
+    /// Open and due on or before the given day
+    pub fn is_due_by(&self, day: NaiveDate) -> bool {
+        !self.is_completed() && self.due_date().is_some_and(|due| due <= day)
+    }
+
+    pub fn needs_reminder(&self, day: NaiveDate) -> bool {
+        !self.reminded && self.is_due_by(day)
+    }
+
+    /// Web links in the description, in order of appearance
+    pub fn urls(&self) -> Vec<&str> {
+        self.desc
+            .split_whitespace()
+            .map(|word| word.trim_start_matches(['(', '[', '<', '"', '\'']))
+            .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+            .map(|word| word.trim_end_matches(['.', ',', ';', ':', ')', ']', '>', '"', '\'']))
+            .collect()
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.desc.unicode_words().count()
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.desc.graphemes(true).count()
+    }
+
+    pub fn toggle_completed(&mut self) {
+        self.completed_at = match self.completed_at {
+            None => Some(timestamp_now()),
+            Some(_) => None,
+        };
+    }
 }
 
 impl fmt::Display for Task {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(&self.desc.to_owned())
+        if self.pinned {
+            f.write_str("⚑ ")?;
+        }
+        if self.priority > 0 {
+            write!(f, "{} ", "!".repeat(self.priority as usize))?;
+        }
+        match self.is_completed() {
+            true => write!(f, "✓ {}", self.desc),
+            false => f.write_str(&self.desc),
+        }
+    }
+}
+
+/// Index of the name equal to the query, or else equal ignoring case and accents
+fn find_named<'n>(mut names: impl Iterator<Item = &'n str> + Clone, query: &str) -> Option<usize> {
+    let normalized = normalize_for_search(query);
+    names
+        .clone()
+        .position(|name| name == query)
+        .or_else(|| names.position(|name| normalize_for_search(name) == normalized))
+}
+
+/// Delay before another password attempt, growing by a second with each failure and then
+/// jumping to `PASSWORD_LOCKOUT_SECS` once `PASSWORD_ATTEMPTS` have failed
+pub fn password_backoff(failures: u32) -> Duration {
+    match failures {
+        n if n < PASSWORD_ATTEMPTS => Duration::from_secs(n.into()),
+        _ => Duration::from_secs(PASSWORD_LOCKOUT_SECS),
+    }
+}
+
+/// How well a name matches a query, ignoring case and accents (lower is better): exact, prefix,
+/// substring, or the query's characters in order. None if the name does not match.
+pub fn match_rank(query: &str, name: &str) -> Option<(u8, usize)> {
+    let (query, name) = (normalize_for_search(query), normalize_for_search(name));
+    let kind = if name == query {
+        0
+    } else if name.starts_with(&query) {
+        1
+    } else if name.contains(&query) {
+        2
+    } else {
+        let mut chars = name.chars();
+        match query.chars().all(|q| chars.any(|c| c == q)) {
+            true => 3,
+            false => return None,
+        }
+    };
+    Some((kind, name.len()))
+}
+
+/// Trimmed, with whitespace control characters (e.g. tabs) as spaces and others removed
+pub fn normalize_name(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c.is_control() {
+            true => c.is_whitespace().then_some(' '),
+            false => Some(c),
+        })
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Whether the file could be a journal, judging by its contents without decrypting
+pub fn is_journal_file(filepath: &Path) -> bool {
+    fs::read(filepath).is_ok_and(|data| is_ciphertext(&data))
+}
+
+pub fn timestamp_now() -> String {
+    Local::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    fn testdata(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/app/testdata")
+            .join(name)
+    }
+
+    fn assert_baseline(journal: &Journal) {
+        assert_eq!(journal.name, "Baseline");
+        let names: Vec<&str> = journal.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Work", "Home"]);
+        assert_eq!(journal.projects.selection(), Some(0));
+        let work = journal.projects.get_item(Some(0)).unwrap();
+        assert!(work.split_vertical);
+        assert_eq!(work.focused_width_percent, 55);
+        let backlog = work.subprojects.get_item(Some(0)).unwrap();
+        assert_eq!(backlog.name, "Backlog");
+        assert_eq!(backlog.tasks.as_strings(), ["Write docs", "✓ Fix bug"]);
+        assert_eq!(backlog.tasks.selection(), Some(1));
+        let fixed = backlog.tasks.get_item(Some(1)).unwrap();
+        assert_eq!(fixed.completed_at.as_deref(), Some("2020-02-03 09:30:00"));
+        assert_eq!((fixed.depth, fixed.priority, fixed.pinned), (0, 0, false));
+        let home = journal.projects.get_item(Some(1)).unwrap();
+        assert_eq!(home.subprojects.selected().unwrap().name, "Chores");
+        assert!(!home.hide_empty && !home.is_locked() && !journal.alphabetical_projects);
+    }
+
+    #[test]
+    fn app_shares_one_data_model() {
+        // Type annotations fail to compile if a second model creeps back in
+        let mut state = App::new(PathBuf::new());
+        let journal: &mut Journal = &mut state.journal;
+        let project: &mut Project = journal.project().unwrap();
+        let subproject: &mut SubProject = project.subprojects.get_item_mut(Some(0)).unwrap();
+        subproject.tasks.add_item(Task::new("Shared"), true);
+        project.subprojects.select(0).unwrap();
+        let task: &Task = state.journal.selected_task().unwrap();
+        assert_eq!(task.desc, "Shared");
+        let markdown = crate::app::export::project_markdown(state.journal.project().unwrap(), 1);
+        assert!(markdown.contains("Shared"));
+    }
+
+    #[test]
+    fn auto_lock_override_is_not_a_setting() {
+        let mut state = App::new(PathBuf::new());
+        state.last_input = Instant::now() - Duration::from_secs(120);
+        assert!(!state.is_inactive());
+        state.auto_lock_after = Some(Duration::from_secs(60));
+        assert!(state.is_inactive());
+        assert!(state.settings == Settings::default());
+        state.settings.auto_lock_minutes = Some(60);
+        assert!(state.is_inactive());
+    }
+
+    #[test]
+    fn password_backoff_grows_then_locks_out() {
+        let secs = |failures| password_backoff(failures).as_secs();
+        assert_eq!((secs(1), secs(2), secs(4)), (1, 2, 4));
+        assert_eq!(secs(PASSWORD_ATTEMPTS), PASSWORD_LOCKOUT_SECS);
+        assert_eq!(secs(PASSWORD_ATTEMPTS + 10), PASSWORD_LOCKOUT_SECS);
+    }
+
+    #[test]
+    fn loads_version_0_journal() {
+        let journal = Journal::load_decrypt(&testdata("journal_v0.dj"), "").unwrap();
+        assert_baseline(&journal);
+        assert!(journal.password.is_empty());
+    }
+
+    #[test]
+    fn loads_version_0_encrypted_journal() {
+        let filepath = testdata("journal_v0_encrypted.dj");
+        assert!(Journal::load_decrypt(&filepath, "").is_err());
+        let journal = Journal::load_decrypt(&filepath, "hunter2").unwrap();
+        assert_baseline(&journal);
+        assert_eq!(&*journal.password, "hunter2");
+    }
+
+    #[test]
+    fn migrated_journal_saves_in_current_version() {
+        let dir = scratch_dir("migrated_journal");
+        let filepath = dir.join("journal");
+        let journal = Journal::load_decrypt(&testdata("journal_v0.dj"), "").unwrap();
+        journal
+            .save_encrypt(&filepath, "", Algorithm::default())
+            .unwrap();
+        let (version, _) = decrypt(&fs::read(&filepath).unwrap(), "").unwrap();
+        assert_eq!(version, FORMAT_VERSION);
+        assert_baseline(&Journal::load_decrypt(&filepath, "").unwrap());
+    }
+
+    #[test]
+    fn saves_and_loads_current_version() {
+        let dir = scratch_dir("current_version");
+        let filepath = dir.join("journal");
+        let mut journal = Journal::new("Current");
+        journal.alphabetical_projects = true;
+        let project = journal.projects.get_item_mut(Some(0)).unwrap();
+        project.hide_empty = true;
+        project.show_ages = true;
+        project.color = Some("red".to_owned());
+        project.save_layout("wide");
+        let subproject = project.subprojects.get_item_mut(Some(0)).unwrap();
+        subproject.sort_key = Some(TaskSortKey::Priority);
+        let mut task = Task::new("Nested");
+        task.depth = 2;
+        task.pinned = true;
+        task.color = Some("blue".to_owned());
+        task.priority = 3;
+        task.due_at = Some("2030-01-01".to_owned());
+        subproject.tasks.add_item(task, true);
+        journal
+            .save_encrypt(&filepath, "secret", Algorithm::ChaCha20Poly1305)
+            .unwrap();
+        let loaded = Journal::load_decrypt(&filepath, "secret").unwrap();
+        assert_eq!(loaded.fingerprint(), journal.fingerprint());
+        let project = loaded.projects.get_item(Some(0)).unwrap();
+        assert!(loaded.alphabetical_projects && project.hide_empty && project.show_ages);
+        assert_eq!(project.layouts[0].name, "wide");
+        let subproject = project.subprojects.get_item(Some(0)).unwrap();
+        assert!(subproject.sort_key == Some(TaskSortKey::Priority));
+        let task = subproject.tasks.selected().unwrap();
+        assert_eq!((task.depth, task.pinned, task.priority), (2, true, 3));
+        assert_eq!(task.due_at.as_deref(), Some("2030-01-01"));
+    }
+
+    #[test]
+    fn saves_locked_and_unlocked_projects() {
+        let dir = scratch_dir("locked_projects");
+        let filepath = dir.join("journal");
+        let mut journal = Journal::new("Mixed");
+        let mut secret = Project::new("Secret");
+        secret.password = Password::from("open sesame");
+        secret
+            .subprojects
+            .get_item_mut(Some(0))
+            .unwrap()
+            .tasks
+            .add_item(Task::new("Hidden"), true);
+        journal.projects.add_item(secret, false);
+        journal
+            .sealed(Algorithm::default())
+            .unwrap()
+            .save_encrypt(&filepath, "", Algorithm::default())
+            .unwrap();
+        let mut loaded = Journal::load_decrypt(&filepath, "").unwrap();
+        assert!(!loaded.projects.get_item(Some(0)).unwrap().is_locked());
+        let secret = loaded.projects.get_item_mut(Some(1)).unwrap();
+        assert!(secret.is_locked() && secret.subprojects.is_empty());
+        let (version, _) = decrypt(secret.locked.as_ref().unwrap(), "open sesame").unwrap();
+        assert_eq!(version, FORMAT_VERSION);
+        assert!(secret.unlock("wrong").is_err());
+        assert!(secret.is_locked());
+        secret.unlock("open sesame").unwrap();
+        assert!(!secret.is_locked());
+        let tasks = &secret.subprojects.get_item(Some(0)).unwrap().tasks;
+        assert_eq!(tasks.as_strings(), ["Hidden"]);
+    }
+
+    #[test]
+    fn rejects_newer_format_version() {
+        let dir = scratch_dir("newer_version");
+        let filepath = dir.join("journal");
+        let encoded = bincode::serialize(&Journal::new("Future")).unwrap();
+        let encrypted = encrypt(&encoded, "", Algorithm::default(), FORMAT_VERSION + 1).unwrap();
+        fs::write(&filepath, encrypted).unwrap();
+        let error = Journal::load_decrypt(&filepath, "").err().unwrap();
+        assert!(matches!(error, LoadError::Unsupported(_)));
+        let expected = format!("unsupported format version [{}]", FORMAT_VERSION + 1);
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[test]
+    fn tells_load_errors_apart() {
+        let dir = scratch_dir("load_errors");
+        let load = |name: &str, key: &str| Journal::load_decrypt(&dir.join(name), key).err();
+        assert!(matches!(load("missing", ""), Some(LoadError::Io(_))));
+        fs::write(dir.join("notes.txt"), "plain text, not a journal").unwrap();
+        assert!(matches!(load("notes.txt", ""), Some(LoadError::NotJournal)));
+        Journal::new("Locked")
+            .save_encrypt(&dir.join("locked"), "secret", Algorithm::default())
+            .unwrap();
+        assert!(matches!(load("locked", "guess"), Some(LoadError::Decrypt)));
+        assert!(load("locked", "secret").is_none());
+        let garbage = encrypt(b"\xff\xff", "", Algorithm::default(), FORMAT_VERSION).unwrap();
+        fs::write(dir.join("corrupt"), garbage).unwrap();
+        assert!(matches!(load("corrupt", ""), Some(LoadError::Corrupt(_))));
+    }
+
+    #[test]
+    fn counts_words_and_characters() {
+        let task = Task::new("  fix   the\tbug,  again ");
+        assert_eq!(task.word_count(), 4);
+        assert_eq!(task.char_count(), 24);
+        let task = Task::new("日本語 テスト");
+        assert_eq!(task.word_count(), 4);
+        assert_eq!(task.char_count(), 7);
+        // Combining marks and emoji sequences are single characters
+        let task = Task::new("cafe\u{301} 👩‍💻");
+        assert_eq!(task.word_count(), 1);
+        assert_eq!(task.char_count(), 6);
+        assert_eq!(Task::new("").word_count(), 0);
+    }
+
+    fn subproject(descs: &[&str]) -> SubProject {
+        let mut subproject = SubProject::new("Tasks");
+        for desc in descs {
+            subproject.tasks.push_item(Task::new(desc));
+        }
+        subproject
+    }
+
+    fn descs(subproject: &SubProject) -> Vec<&str> {
+        subproject
+            .tasks
+            .iter()
+            .map(|task| task.desc.as_str())
+            .collect()
+    }
+
+    fn depths(subproject: &SubProject) -> Vec<u8> {
+        subproject.tasks.iter().map(|task| task.depth).collect()
+    }
+
+    #[test]
+    fn indents_at_most_one_level_below_the_task_above() {
+        let mut subproject = subproject(&["Parent", "Child", "Grandchild"]);
+        subproject.tasks.select(0).unwrap();
+        subproject.indent_task();
+        assert_eq!(depths(&subproject), [0, 0, 0]);
+        subproject.tasks.select(1).unwrap();
+        subproject.indent_task();
+        subproject.indent_task();
+        assert_eq!(depths(&subproject), [0, 1, 0]);
+        subproject.tasks.select(2).unwrap();
+        for _ in 0..5 {
+            subproject.indent_task();
+        }
+        assert_eq!(depths(&subproject), [0, 1, 2]);
+        subproject.tasks.select(1).unwrap();
+        for _ in 0..3 {
+            subproject.outdent_task();
+        }
+        assert_eq!(depths(&subproject), [0, 0, 2]);
+    }
+
+    #[test]
+    fn completing_a_parent_completes_its_subtasks() {
+        let mut subproject = subproject(&["Parent", "Child", "Sibling"]);
+        subproject.tasks.select(1).unwrap();
+        subproject.indent_task();
+        subproject.tasks.select(0).unwrap();
+        subproject.toggle_task_completed();
+        let completed: Vec<bool> = subproject.tasks.iter().map(Task::is_completed).collect();
+        assert_eq!(completed, [true, true, false]);
+    }
+
+    #[test]
+    fn project_round_trip_resets_session_state() {
+        let filepath = scratch_dir("project_round_trip").join("project");
+        let mut project = Project::new("Exported");
+        project.split_vertical = true;
+        project.subprojects.select(0).unwrap();
+        let subproject = project.subproject().unwrap();
+        subproject.name = "Inbox".to_owned();
+        subproject.tasks.push_item(Task::new("Keep"));
+        subproject.tasks.get_item_mut(Some(0)).unwrap().marked = true;
+        project.prompt.set_text("half typed");
+        project.prompt_request = Some(JournalPrompt::RenameTask);
+        project.filter = Some(TaskFilter::new("Keep").unwrap());
+        project
+            .save_encrypt(&filepath, "key", Algorithm::default())
+            .unwrap();
+        let mut loaded = Project::load_decrypt(&filepath, "key").unwrap();
+        assert_eq!(loaded.name, "Exported");
+        assert!(loaded.split_vertical);
+        let subproject = loaded.subprojects.get_item(Some(0)).unwrap();
+        assert_eq!(subproject.name, "Inbox");
+        assert_eq!(subproject.tasks.as_strings(), ["Keep"]);
+        assert!(!subproject.tasks.get_item(Some(0)).unwrap().marked);
+        assert!(loaded.prompt_request.is_none() && loaded.filter.is_none());
+        assert_eq!(loaded.prompt.get_text(), "");
+        assert!(matches!(
+            Project::load_decrypt(&filepath, "wrong"),
+            Err(LoadError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn pinned_tasks_stay_in_front_in_order() {
+        let mut subproject = subproject(&["a", "b", "c", "d"]);
+        subproject.tasks.select(2).unwrap();
+        subproject.toggle_task_pinned();
+        subproject.tasks.select(3).unwrap();
+        subproject.toggle_task_pinned();
+        assert_eq!(descs(&subproject), ["c", "d", "a", "b"]);
+        // The pinned task stays selected
+        assert_eq!(subproject.tasks.selected().unwrap().desc, "d");
+        subproject.tasks.select(0).unwrap();
+        subproject.toggle_task_pinned();
+        assert_eq!(descs(&subproject), ["d", "c", "a", "b"]);
+        assert_eq!(subproject.tasks.selected().unwrap().desc, "c");
+        // Sorting keeps pinned tasks first, in their order
+        for (task, created_at) in subproject.tasks.iter_mut().zip(["4", "3", "2", "1"]) {
+            task.created_at = created_at.to_owned();
+        }
+        subproject.sort_key = Some(TaskSortKey::Created);
+        subproject.sort_tasks();
+        assert_eq!(descs(&subproject), ["d", "b", "a", "c"]);
+    }
+
+    fn completed(subproject: &SubProject) -> Vec<bool> {
+        subproject.tasks.iter().map(Task::is_completed).collect()
+    }
+
+    #[test]
+    fn toggles_marked_tasks_by_majority() {
+        let mut subproject = subproject(&["a", "b", "c", "d"]);
+        for index in [0, 1, 2] {
+            subproject.tasks.get_item_mut(Some(index)).unwrap().marked = true;
+        }
+        subproject
+            .tasks
+            .get_item_mut(Some(0))
+            .unwrap()
+            .toggle_completed();
+        // Mostly open: complete all marked
+        assert_eq!(subproject.toggle_marked_completed(), (true, 3));
+        assert_eq!(completed(&subproject), [true, true, true, false]);
+        // Mostly completed: reopen all marked
+        subproject
+            .tasks
+            .get_item_mut(Some(1))
+            .unwrap()
+            .toggle_completed();
+        assert_eq!(subproject.toggle_marked_completed(), (false, 3));
+        assert_eq!(completed(&subproject), [false; 4]);
+        // A tie completes
+        subproject.tasks.get_item_mut(Some(2)).unwrap().marked = false;
+        subproject
+            .tasks
+            .get_item_mut(Some(0))
+            .unwrap()
+            .toggle_completed();
+        assert_eq!(subproject.toggle_marked_completed(), (true, 2));
+    }
+
+    #[test]
+    fn filters_by_regex_or_substring() {
+        let task = Task::new("Fix bug #42 in Café menu");
+        let matches = |pattern: &str| TaskFilter::new(pattern).unwrap().matches(&task);
+        assert!(matches(r"re:#\d+"));
+        assert!(matches("re:^Fix"));
+        assert!(!matches("re:^bug"));
+        // Without the prefix, a case and accent insensitive substring
+        assert!(matches("cafe"));
+        assert!(!matches(r"#\d+"));
+        assert!(TaskFilter::new("re:(unclosed").is_err());
+        assert_eq!(TaskFilter::new(r"re:\d").unwrap().pattern(), r"re:\d");
+    }
+
+    #[test]
+    fn finds_tasks_due_today_or_overdue() {
+        let today = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+        let due = |due_at: Option<&str>| Task {
+            due_at: due_at.map(str::to_owned),
+            ..Task::new("Task")
+        };
+        assert!(due(Some("2023-05-10")).is_due_by(today));
+        assert!(due(Some("2023-04-30")).is_due_by(today));
+        assert!(!due(Some("2023-05-11")).is_due_by(today));
+        assert!(!due(None).is_due_by(today));
+        let mut done = due(Some("2023-05-01"));
+        done.toggle_completed();
+        assert!(!done.is_due_by(today));
+        let mut journal = Journal::new("Today");
+        let mut second = Project::new("Second");
+        let tasks = &mut second.subprojects.get_item_mut(Some(0)).unwrap().tasks;
+        tasks.push_item(due(Some("2023-05-11")));
+        tasks.push_item(due(Some("2023-05-09")));
+        journal.projects.push_item(second);
+        let locations: Vec<(usize, usize, usize)> = journal
+            .due_tasks(today)
+            .iter()
+            .map(|l| (l.project, l.subproject, l.task))
+            .collect();
+        assert_eq!(locations, [(1, 0, 1)]);
+    }
+
+    #[test]
+    fn reminds_of_due_tasks_once() {
+        let today = NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+        let mut task = Task {
+            due_at: Some("2023-05-10".to_owned()),
+            ..Task::new("Task")
+        };
+        assert!(!task.needs_reminder(today.pred_opt().unwrap()));
+        assert!(task.needs_reminder(today));
+        task.reminded = true;
+        assert!(!task.needs_reminder(today));
+        // The guard is per session
+        let mut journal = Journal::new("Reminders");
+        journal
+            .projects
+            .get_item_mut(Some(0))
+            .unwrap()
+            .subprojects
+            .get_item_mut(Some(0))
+            .unwrap()
+            .tasks
+            .push_item(task);
+        let dir = scratch_dir("reminded");
+        journal
+            .save_encrypt(&dir.join("journal"), "", Algorithm::default())
+            .unwrap();
+        let loaded = Journal::load_decrypt(&dir.join("journal"), "").unwrap();
+        let project = loaded.projects.get_item(Some(0)).unwrap();
+        let task = project
+            .subprojects
+            .get_item(Some(0))
+            .unwrap()
+            .tasks
+            .get_item(Some(0))
+            .unwrap();
+        assert!(task.needs_reminder(today));
+    }
+
+    #[test]
+    fn extracts_urls_from_description() {
+        let task = Task::new(
+            "See https://example.com/a?b=1, and (http://docs.rs/tui). Not ftp://x or example.org",
+        );
+        assert_eq!(
+            task.urls(),
+            ["https://example.com/a?b=1", "http://docs.rs/tui"]
+        );
+        assert!(Task::new("No links here").urls().is_empty());
+    }
+
+    #[test]
+    fn finds_project_by_best_match() {
+        let mut journal = Journal::new("Projects");
+        journal.projects = ["Website", "Web", "Café Menu", "Backend"]
+            .map(Project::new)
+            .to_vec()
+            .into();
+        assert_eq!(journal.find_project("web"), Some(1));
+        assert_eq!(journal.find_project("Webs"), Some(0));
+        assert_eq!(journal.find_project("cafe"), Some(2));
+        assert_eq!(journal.find_project("end"), Some(3));
+        assert_eq!(journal.find_project("bknd"), Some(3));
+        assert_eq!(journal.find_project("xyz"), None);
+    }
+
+    #[test]
+    fn normalizes_names() {
+        assert_eq!(normalize_name("  Fix bug \n"), "Fix bug");
+        assert_eq!(normalize_name("Fix\tbug"), "Fix bug");
+        assert_eq!(normalize_name("Fix\u{7}\u{1b}[31m bug"), "Fix[31m bug");
+        assert_eq!(normalize_name(" \t\u{0}"), "");
+    }
+
+    #[test]
+    fn saves_and_cycles_layout_presets() {
+        let mut project = Project::new("Layouts");
+        project.subprojects = ["Left", "Right"].map(SubProject::new).to_vec().into();
+        assert_eq!(project.cycle_layout(), None);
+        project.subprojects.select(0).unwrap();
+        project.focused_width_percent = 70;
+        project.save_layout("Wide");
+        project.subprojects.select(1).unwrap();
+        project.split_vertical = true;
+        project.focused_width_percent = 40;
+        project.save_layout("Stacked");
+        // Saving under a taken name replaces the preset
+        project.focused_width_percent = 50;
+        project.save_layout("Stacked");
+        assert_eq!(project.layouts.len(), 2);
+        assert_eq!(project.cycle_layout().as_deref(), Some("Wide"));
+        assert!(!project.split_vertical);
+        assert_eq!(project.focused_width_percent, 70);
+        assert_eq!(project.subprojects.selection(), Some(0));
+        assert_eq!(project.cycle_layout().as_deref(), Some("Stacked"));
+        assert!(project.split_vertical);
+        assert_eq!(project.focused_width_percent, 50);
+        assert_eq!(project.subprojects.selection(), Some(1));
+        assert_eq!(project.cycle_layout().as_deref(), Some("Wide"));
+    }
+
+    #[test]
+    fn resolves_task_paths() {
+        let mut journal = Journal::new("Paths");
+        let mut work = Project::new("Work");
+        work.subprojects = vec![subproject(&["Fix bug", "Café\nnotes"])].into();
+        work.subprojects.get_item_mut(Some(0)).unwrap().name = "Backlog".to_owned();
+        journal.projects = vec![Project::new("Home"), work].into();
+        assert_eq!(journal.resolve_path("Work").unwrap(), [1]);
+        assert_eq!(
+            journal.resolve_path("work/backlog/cafe").unwrap(),
+            [1, 0, 1]
+        );
+        let error = |path: &str| journal.resolve_path(path).unwrap_err().to_string();
+        assert_eq!(error("Play"), "project `Play` not found");
+        assert_eq!(error("Work/Done"), "subproject `Done` not found in `Work`");
+        assert_eq!(
+            error("Work/Backlog/Ship"),
+            "task `Ship` not found in `Backlog`"
+        );
+        assert_eq!(error("a/b/c/d"), "too many segments in `a/b/c/d`");
+        journal.projects.select(0).unwrap();
+        assert!(journal.select_path("Work/Done").is_err());
+        assert_eq!(journal.projects.selection(), Some(0));
+        journal.select_path("Work/Backlog/Fix bug").unwrap();
+        assert_eq!(journal.selected_task().unwrap().desc, "Fix bug");
+    }
+
+    #[test]
+    fn clears_only_completed_tasks() {
+        let mut subproject = subproject(&["Open", "Done", "Also open", "Done too", "Last"]);
+        for index in [1, 3, 4] {
+            subproject
+                .tasks
+                .get_item_mut(Some(index))
+                .unwrap()
+                .completed_at = Some(timestamp_now());
+        }
+        subproject.tasks.select(4).unwrap();
+        assert_eq!(subproject.clear_completed(), 3);
+        assert_eq!(descs(&subproject), ["Open", "Also open"]);
+        let selection = subproject.tasks.selection().unwrap();
+        assert!(selection < subproject.tasks.len());
+        assert_eq!(subproject.clear_completed(), 0);
     }
 }
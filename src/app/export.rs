@@ -0,0 +1,456 @@
+use super::data::{
+    DataDeserialize, Error, Journal, Project, Result, SubProject, Task, DATE_FORMAT,
+    PRIORITY_LABELS, TASK_MAX_DEPTH,
+};
+use crate::crypto::{Algorithm, Password};
+use chrono::{NaiveDate, Utc};
+use std::path::PathBuf;
+
+/// Subfolder of the data directory that exports are written to
+pub const EXPORTS_DIR: &str = "exports";
+/// Suffix of the plaintext mirror written next to a journal file
+pub const MIRROR_SUFFIX: &str = ".mirror.md";
+/// Shown in place of the contents of password protected projects
+const PROTECTED_PLACEHOLDER: &str = "(password protected)";
+
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    ICalendar,
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [Self::Csv, Self::ICalendar, Self::Json];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::ICalendar => "iCalendar (tasks with due dates)",
+            Self::Json => "JSON (full journal)",
+        }
+    }
+
+    pub fn export(&self, journal: &Journal) -> Result<String> {
+        match self {
+            Self::Csv => to_csv(journal),
+            Self::ICalendar => Ok(to_ical(journal)),
+            Self::Json => to_json(journal),
+        }
+    }
+}
+
+/// Whether the project contents are kept out of plaintext exports
+fn is_protected(project: &Project) -> bool {
+    !project.password.is_empty() || project.is_locked()
+}
+
+/// All tasks of the journal as CSV, one row per task, and a placeholder row for each password
+/// protected project
+pub fn to_csv(journal: &Journal) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record([
+        "journal",
+        "project",
+        "subproject",
+        "description",
+        "created_at",
+        "completed_at",
+        "priority",
+        "due_at",
+    ])?;
+    for project in journal.projects.iter() {
+        if is_protected(project) {
+            let mut record = vec![""; 8];
+            record[0] = &journal.name;
+            record[1] = &project.name;
+            record[3] = PROTECTED_PLACEHOLDER;
+            writer.write_record(record)?;
+            continue;
+        }
+        for subproject in project.subprojects.iter() {
+            for task in subproject.tasks.iter() {
+                writer.write_record([
+                    journal.name.as_str(),
+                    &project.name,
+                    &subproject.name,
+                    &task.desc,
+                    &task.created_at,
+                    task.completed_at.as_deref().unwrap_or_default(),
+                    PRIORITY_LABELS
+                        .get(task.priority as usize)
+                        .copied()
+                        .unwrap_or_default(),
+                    task.due_at.as_deref().unwrap_or_default(),
+                ])?;
+            }
+        }
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| Error::from(e.to_string()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Tasks with a due date as iCalendar to-dos, leaving out password protected projects
+pub fn to_ical(journal: &Journal) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//devjournal//EN".to_owned(),
+    ];
+    for project in journal.projects.iter().filter(|p| !is_protected(p)) {
+        for subproject in project.subprojects.iter() {
+            for (index, task) in subproject.tasks.iter().enumerate() {
+                let Some(due) = task
+                    .due_at
+                    .as_deref()
+                    .and_then(|due| NaiveDate::parse_from_str(due, DATE_FORMAT).ok())
+                else {
+                    continue;
+                };
+                // Tasks added together share a creation time
+                let twins = subproject
+                    .tasks
+                    .iter()
+                    .take(index)
+                    .filter(|t| t.created_at == task.created_at)
+                    .count();
+                let uid = fnv1a(
+                    format!(
+                        "{}\0{}\0{}\0{twins}",
+                        project.name, subproject.name, task.created_at
+                    )
+                    .as_bytes(),
+                );
+                lines.push("BEGIN:VTODO".to_owned());
+                lines.push(format!("UID:{uid:016x}@devjournal"));
+                lines.push(format!("DTSTAMP:{stamp}"));
+                lines.push(format!("SUMMARY:{}", ical_escape(&task.desc)));
+                lines.push(format!("CATEGORIES:{}", ical_escape(&project.name)));
+                lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+                if task.is_completed() {
+                    lines.push("STATUS:COMPLETED".to_owned());
+                }
+                lines.push("END:VTODO".to_owned());
+            }
+        }
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    lines.iter().map(|line| ical_fold(line) + "\r\n").collect()
+}
+
+/// 64-bit FNV-1a, which unlike the standard library hashers is the same on every build
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold lines longer than 75 octets, continuing with a leading space
+fn ical_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// The whole journal as JSON, with password-protected projects sealed
+pub fn to_json(journal: &Journal) -> Result<String> {
+    let mut journal = journal.sealed(Algorithm::default())?;
+    journal.password = Password::default();
+    Ok(serde_json::to_string_pretty(&journal)?)
+}
+
+/// A journal previously exported as JSON
+pub fn load_json<'a>(filepath: &PathBuf) -> Result<Journal<'a>> {
+    let journal = Journal::load_json(filepath)?;
+    validate(&journal)?;
+    Ok(journal)
+}
+
+/// Check what the JSON schema alone cannot
+fn validate(journal: &Journal) -> Result<()> {
+    let selection_valid = |selection: Option<usize>, len: usize| selection.is_none_or(|i| i < len);
+    if !selection_valid(journal.projects.selection(), journal.projects.len()) {
+        return Error::from("project selection out of range").into();
+    }
+    for project in journal.projects.iter() {
+        let invalid = |message: &str| Error::from(format!("project `{}`: {message}", project.name));
+        if !selection_valid(project.subprojects.selection(), project.subprojects.len()) {
+            return invalid("subproject selection out of range").into();
+        }
+        for subproject in project.subprojects.iter() {
+            if !selection_valid(subproject.tasks.selection(), subproject.tasks.len()) {
+                return invalid("task selection out of range").into();
+            }
+            for task in subproject.tasks.iter() {
+                if task.depth > TASK_MAX_DEPTH {
+                    return invalid(&format!("task `{}` nested too deep", task.desc)).into();
+                }
+                if task.priority as usize >= PRIORITY_LABELS.len() {
+                    return invalid(&format!("task `{}` has unknown priority", task.desc)).into();
+                }
+                if let Some(due) = &task.due_at {
+                    if NaiveDate::parse_from_str(due, DATE_FORMAT).is_err() {
+                        return invalid(&format!("task `{}` has invalid due date", task.desc))
+                            .into();
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The journal as Markdown for diffing, omitting anything that changes without edits
+/// (selections, encrypted data) so that identical content gives identical text
+pub fn to_markdown(journal: &Journal) -> String {
+    let mut text = format!("# {}\n", journal.name);
+    for project in journal.projects.iter() {
+        text.push('\n');
+        if is_protected(project) {
+            text.push_str(&format!("## {}\n\n{PROTECTED_PLACEHOLDER}\n", project.name));
+            continue;
+        }
+        text.push_str(&project_markdown(project, 2));
+    }
+    text
+}
+
+/// The project under a heading of the given level, its subprojects one level below
+pub fn project_markdown(project: &Project, level: usize) -> String {
+    let mut text = format!("{} {}\n", "#".repeat(level), project.name);
+    for subproject in project.subprojects.iter() {
+        text.push('\n');
+        text.push_str(&subproject_markdown(subproject, level + 1));
+    }
+    text
+}
+
+/// The subproject under a heading of the given level, its tasks as a checklist
+pub fn subproject_markdown(subproject: &SubProject, level: usize) -> String {
+    format!(
+        "{} {}\n\n{}",
+        "#".repeat(level),
+        subproject.name,
+        tasks_markdown(subproject.tasks.iter())
+    )
+}
+
+/// The tasks as a checklist, nested by depth
+pub fn tasks_markdown<'t>(tasks: impl Iterator<Item = &'t Task>) -> String {
+    let mut text = String::new();
+    for task in tasks {
+        let indent = "  ".repeat(task.depth as usize);
+        let check = match task.is_completed() {
+            true => 'x',
+            false => ' ',
+        };
+        let mut lines = task.desc.lines();
+        text.push_str(&format!(
+            "{indent}- [{check}] {}",
+            lines.next().unwrap_or_default()
+        ));
+        if let Some(due) = &task.due_at {
+            text.push_str(&format!(" (due {due})"));
+        }
+        text.push('\n');
+        for line in lines {
+            text.push_str(&format!("{indent}      {line}\n"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+    use std::fs;
+
+    /// A project with a task that needs quoting, a completed task due some day, and a task
+    /// without a due date
+    fn fixture() -> Journal<'static> {
+        let mut work = Project::new("Work");
+        work.subprojects = vec![SubProject::new("Backlog")].into();
+        let tasks = &mut work.subprojects.get_item_mut(Some(0)).unwrap().tasks;
+        tasks.push_item(Task {
+            created_at: "2023-01-02 10:00:00".to_owned(),
+            priority: 2,
+            ..Task::new("Write docs, then \"ship\"")
+        });
+        tasks.push_item(Task {
+            created_at: "2023-01-03 11:00:00".to_owned(),
+            completed_at: Some("2023-01-04 12:00:00".to_owned()),
+            due_at: Some("2023-02-01".to_owned()),
+            ..Task::new("Release; v1")
+        });
+        let mut journal = Journal::new("Export");
+        journal.projects = vec![work].into();
+        journal
+    }
+
+    #[test]
+    fn exports_csv_rows() {
+        let csv = to_csv(&fixture()).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            rows,
+            [
+                "journal,project,subproject,description,created_at,completed_at,priority,due_at",
+                "Export,Work,Backlog,\"Write docs, then \"\"ship\"\"\",2023-01-02 10:00:00,,Medium,",
+                "Export,Work,Backlog,Release; v1,2023-01-03 11:00:00,2023-01-04 12:00:00,None,2023-02-01",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_protected_projects_out_of_csv() {
+        let mut journal = fixture();
+        journal.projects.get_item_mut(Some(0)).unwrap().password = Password::from("secret");
+        let csv = to_csv(&journal).unwrap();
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(rows, ["Export,Work,,(password protected),,,,"]);
+        assert_eq!(to_ical(&journal).matches("BEGIN:VTODO").count(), 0);
+    }
+
+    #[test]
+    fn exports_due_tasks_as_ical_todos() {
+        let ics = to_ical(&fixture());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        // Only the task with a due date
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 1);
+        let lines: Vec<&str> = ics.lines().collect();
+        assert!(lines.contains(&"SUMMARY:Release\\; v1"));
+        assert!(lines.contains(&"DUE;VALUE=DATE:20230201"));
+        assert!(lines.contains(&"STATUS:COMPLETED"));
+        // The same task keeps its identity across exports
+        let uid = |ics: &str| {
+            ics.lines()
+                .find(|l| l.starts_with("UID:"))
+                .map(str::to_owned)
+        };
+        assert!(uid(&ics).is_some());
+        assert_eq!(uid(&ics), uid(&to_ical(&fixture())));
+        // Editing the task does not change it
+        let mut journal = fixture();
+        let project = journal.projects.get_item_mut(Some(0)).unwrap();
+        for task in project
+            .subprojects
+            .get_item_mut(Some(0))
+            .unwrap()
+            .tasks
+            .iter_mut()
+        {
+            task.desc.push_str(" (edited)");
+        }
+        assert_eq!(uid(&ics), uid(&to_ical(&journal)));
+    }
+
+    #[test]
+    fn hashes_ical_uids_with_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn folds_long_ical_lines() {
+        let folded = ical_fold(&"x".repeat(160));
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(
+            lines.iter().map(|l| l.len()).collect::<Vec<_>>(),
+            [75, 75, 12]
+        );
+        assert!(lines[1].starts_with(' ') && lines[2].starts_with(' '));
+    }
+
+    #[test]
+    fn json_export_loads_back() {
+        let dir = scratch_dir("json_round_trip");
+        let filepath = dir.join("journal.json");
+        fs::write(&filepath, to_json(&fixture()).unwrap()).unwrap();
+        let journal = load_json(&filepath).unwrap();
+        assert_eq!(journal.name, "Export");
+        let project = journal.projects.get_item(Some(0)).unwrap();
+        let tasks = &project.subprojects.get_item(Some(0)).unwrap().tasks;
+        let descs: Vec<&str> = tasks.iter().map(|task| task.desc.as_str()).collect();
+        assert_eq!(descs, ["Write docs, then \"ship\"", "Release; v1"]);
+        assert_eq!(
+            tasks.get_item(Some(1)).unwrap().due_at.as_deref(),
+            Some("2023-02-01")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        let dir = scratch_dir("json_malformed");
+        let filepath = dir.join("journal.json");
+        fs::write(&filepath, "{\"name\": \"Broken\"").unwrap();
+        assert!(load_json(&filepath).is_err());
+        let mut journal = fixture();
+        let project = journal.projects.get_item_mut(Some(0)).unwrap();
+        let tasks = &mut project.subprojects.get_item_mut(Some(0)).unwrap().tasks;
+        tasks.get_item_mut(Some(0)).unwrap().due_at = Some("next week".to_owned());
+        fs::write(&filepath, to_json(&journal).unwrap()).unwrap();
+        let error = load_json(&filepath).err().unwrap();
+        assert!(error.to_string().contains("invalid due date"));
+    }
+
+    #[test]
+    fn exports_scoped_markdown() {
+        let mut journal = fixture();
+        let project = journal.projects.get_item_mut(Some(0)).unwrap();
+        let subproject = project.subprojects.get_item_mut(Some(0)).unwrap();
+        subproject.tasks.push_item(Task {
+            depth: 1,
+            ..Task::new("Proofread\nTwice")
+        });
+        assert_eq!(
+            subproject_markdown(subproject, 2),
+            "## Backlog\n\n\
+             - [ ] Write docs, then \"ship\"\n\
+             - [x] Release; v1 (due 2023-02-01)\n  \
+             - [ ] Proofread\n        \
+             Twice\n"
+        );
+        project.subprojects.push_item(SubProject::new("Done"));
+        let markdown = project_markdown(project, 1);
+        assert!(markdown.starts_with("# Work\n\n## Backlog\n\n"));
+        assert!(markdown.ends_with("\n## Done\n\n"));
+        assert!(!markdown.contains("Export"));
+    }
+
+    #[test]
+    fn exports_only_marked_tasks() {
+        let mut journal = fixture();
+        let project = journal.projects.get_item_mut(Some(0)).unwrap();
+        let tasks = &mut project.subprojects.get_item_mut(Some(0)).unwrap().tasks;
+        tasks.push_item(Task::new("Unmarked"));
+        tasks.push_item(Task::new("Marked"));
+        for index in [1, 3] {
+            tasks.get_item_mut(Some(index)).unwrap().marked = true;
+        }
+        assert_eq!(
+            tasks_markdown(tasks.iter().filter(|task| task.marked)),
+            "- [x] Release; v1 (due 2023-02-01)\n- [ ] Marked\n"
+        );
+        assert_eq!(tasks_markdown(std::iter::empty()), "");
+    }
+}
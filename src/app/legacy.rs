@@ -0,0 +1,95 @@
+// Data layouts of earlier file format versions, frozen so that old files can still be read
+use super::data::{Journal, Project, SubProject, Task};
+use super::list::SelectionList;
+use crate::crypto::Password;
+use serde::Deserialize;
+
+/// `SelectionList` as of version 0
+#[derive(Deserialize)]
+struct ListV0<T> {
+    items: Vec<T>,
+    selection: Option<usize>,
+}
+
+impl<T> ListV0<T> {
+    fn migrate<U>(self, f: impl FnMut(T) -> U) -> SelectionList<U> {
+        let mut list = SelectionList::from(self.items.into_iter().map(f).collect::<Vec<U>>());
+        if let Some(index) = self.selection {
+            list.select(index).ok();
+        }
+        list
+    }
+}
+
+#[derive(Deserialize)]
+pub struct JournalV0 {
+    name: String,
+    password: String,
+    projects: ListV0<ProjectV0>,
+}
+
+impl<'a> From<JournalV0> for Journal<'a> {
+    fn from(value: JournalV0) -> Self {
+        Self {
+            name: value.name,
+            password: Password::from(value.password),
+            projects: value.projects.migrate(Project::from),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ProjectV0 {
+    name: String,
+    password: String,
+    subprojects: ListV0<SubProjectV0>,
+    focused_width_percent: u16,
+    split_vertical: bool,
+}
+
+impl<'a> From<ProjectV0> for Project<'a> {
+    fn from(value: ProjectV0) -> Self {
+        Self {
+            name: value.name,
+            password: Password::from(value.password),
+            subprojects: value.subprojects.migrate(SubProject::from),
+            focused_width_percent: value.focused_width_percent,
+            split_vertical: value.split_vertical,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SubProjectV0 {
+    name: String,
+    tasks: ListV0<TaskV0>,
+}
+
+impl From<SubProjectV0> for SubProject {
+    fn from(value: SubProjectV0) -> Self {
+        Self {
+            name: value.name,
+            tasks: value.tasks.migrate(Task::from),
+            sort_key: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TaskV0 {
+    desc: String,
+    created_at: String,
+    completed_at: Option<String>,
+}
+
+impl From<TaskV0> for Task {
+    fn from(value: TaskV0) -> Self {
+        Self {
+            created_at: value.created_at,
+            completed_at: value.completed_at,
+            ..Task::new(&value.desc)
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use crate::app::data::{Error, Result};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, ops::Add, slice::Iter};
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::Add,
+    slice::{Iter, IterMut},
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SelectionList<T> {
@@ -31,10 +36,18 @@ impl<T> SelectionList<T> {
         self.items.iter()
     }
 
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.items.iter_mut()
+    }
+
     pub fn len(&self) -> usize {
         self.items.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
     pub fn get_item(&self, index: Option<usize>) -> Option<&T> {
         match index {
             Some(i) => match i < self.items.len() {
@@ -156,57 +169,121 @@ impl<T> SelectionList<T> {
         self.selection = self.prev_index()
     }
 
+    /// Select the next item, or stay on the last item if not wrapping around
+    pub fn step_next(&mut self, wrap: bool) {
+        if wrap || self.selection.is_none_or(|index| index + 1 < self.len()) {
+            self.select_next();
+        }
+    }
+
+    /// Select the previous item, or stay on the first item if not wrapping around
+    pub fn step_prev(&mut self, wrap: bool) {
+        if wrap || self.selection.is_none_or(|index| index > 0) {
+            self.select_prev();
+        }
+    }
+
     pub fn shift_next(&mut self) -> Result<usize> {
-        if let Some(selected) = self.selection {
-            if selected < self.items.len() - 1 {
-                let new_index = selected + 1;
-                self.items.swap(selected, new_index);
-                self.selection = Some(new_index);
-                Ok(new_index)
-            } else {
-                let element = self.items.pop().ok_or(Error::from(
-                    "selection is Some, should have at least one item",
-                ))?;
+        let selected = self.selection.ok_or(Error::from("no item selected"))?;
+        if self.items.len() < 2 {
+            return Ok(selected);
+        };
+        let new_index = match selected + 1 < self.items.len() {
+            true => {
+                self.items.swap(selected, selected + 1);
+                selected + 1
+            }
+            false => {
+                let element = self.items.remove(selected);
                 self.items.insert(0, element);
-                self.selection = Some(0);
-                Ok(0)
+                0
             }
-        } else {
-            Err(Error::from("no item selected"))
-        }
+        };
+        self.selection = Some(new_index);
+        Ok(new_index)
     }
 
     pub fn shift_prev(&mut self) -> Result<usize> {
-        if let Some(selected) = self.selection {
-            if selected > 0 {
-                let new_index = selected - 1;
-                self.items.swap(selected, new_index);
-                self.selection = Some(new_index);
-                Ok(new_index)
-            } else {
+        let selected = self.selection.ok_or(Error::from("no item selected"))?;
+        if self.items.len() < 2 {
+            return Ok(selected);
+        };
+        let new_index = match selected > 0 {
+            true => {
+                self.items.swap(selected, selected - 1);
+                selected - 1
+            }
+            false => {
                 let element = self.items.remove(selected);
                 self.items.push(element);
-                self.selection = Some(self.items.len() - 1);
-                Ok(self.items.len() - 1)
+                self.items.len() - 1
             }
-        } else {
-            Err(Error::from("no item selected"))
-        }
+        };
+        self.selection = Some(new_index);
+        Ok(new_index)
+    }
+
+    pub fn move_to(&mut self, target: usize) -> Result<()> {
+        let selected = self.selection.ok_or(Error::from("no item selected"))?;
+        if target >= self.items.len() {
+            return Err(Error::from("index out of range"));
+        };
+        let item = self.items.remove(selected);
+        self.items.insert(target, item);
+        self.selection = Some(target);
+        Ok(())
     }
 
     pub fn pop_selected(&mut self) -> Option<T> {
-        match self.selection {
-            None => None,
-            Some(index) => {
-                let result = self.items.remove(index);
-                if self.items.is_empty() {
-                    self.selection = None;
-                } else if index >= self.items.len() {
-                    self.selection = Some(self.items.len() - 1);
-                }
-                Some(result)
+        let selected = self.selection?;
+        let mut index = 0;
+        self.retain(|_| {
+            index += 1;
+            index - 1 != selected
+        })
+        .pop()
+    }
+
+    /// Stable sort of the items, keeping the same item selected
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut indexed: Vec<(usize, T)> = std::mem::take(&mut self.items)
+            .into_iter()
+            .enumerate()
+            .collect();
+        indexed.sort_by(|(_, a), (_, b)| compare(a, b));
+        self.selection = self
+            .selection
+            .and_then(|selected| indexed.iter().position(|(index, _)| *index == selected));
+        self.items = indexed.into_iter().map(|(_, item)| item).collect();
+    }
+
+    /// Keep only the items matching the predicate, returning the removed items in order. If the
+    /// selected item is removed, the selection moves to the next remaining item (or the last).
+    pub fn retain<F>(&mut self, mut predicate: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut kept = Vec::with_capacity(self.items.len());
+        let mut removed = Vec::new();
+        let mut selection = None;
+        for (index, item) in std::mem::take(&mut self.items).into_iter().enumerate() {
+            if self.selection == Some(index) {
+                selection = Some(kept.len());
+            }
+            match predicate(&item) {
+                true => kept.push(item),
+                false => removed.push(item),
             }
         }
+        self.items = kept;
+        self.selection = match self.items.is_empty() {
+            true => None,
+            false => selection.map(|index| index.min(self.items.len() - 1)),
+        };
+        removed
     }
 }
 
@@ -216,11 +293,15 @@ where
 {
     type Output = SelectionList<T>;
 
+    /// Appends the items of `rhs`, keeping the selection of `self`
     fn add(self, rhs: SelectionList<T>) -> Self::Output {
         let mut items = self.items;
         let mut rhs_items = rhs.items;
         items.append(&mut rhs_items);
-        SelectionList::from(items)
+        SelectionList {
+            items,
+            selection: self.selection,
+        }
     }
 }
 
@@ -232,3 +313,120 @@ where
         self.iter().map(|t| t.to_string()).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Items 0 to len-1, with the given selection
+    fn list(len: usize, selection: Option<usize>) -> SelectionList<usize> {
+        let mut list = SelectionList::from((0..len).collect::<Vec<usize>>());
+        list.selection = selection;
+        list
+    }
+
+    #[test]
+    fn move_to_forward_and_backward() {
+        let mut forward = list(5, Some(1));
+        forward.move_to(3).unwrap();
+        assert_eq!(forward.items, [0, 2, 3, 1, 4]);
+        assert_eq!(forward.selection(), Some(3));
+        let mut backward = list(5, Some(3));
+        backward.move_to(1).unwrap();
+        assert_eq!(backward.items, [0, 3, 1, 2, 4]);
+        assert_eq!(backward.selection(), Some(1));
+    }
+
+    #[test]
+    fn move_to_the_ends() {
+        let mut first = list(4, Some(2));
+        first.move_to(0).unwrap();
+        assert_eq!(first.items, [2, 0, 1, 3]);
+        let mut last = list(4, Some(1));
+        last.move_to(3).unwrap();
+        assert_eq!(last.items, [0, 2, 3, 1]);
+        assert_eq!(last.selection(), Some(3));
+    }
+
+    #[test]
+    fn move_to_out_of_range() {
+        let mut items = list(3, Some(1));
+        assert!(items.move_to(3).is_err());
+        assert_eq!(items.items, [0, 1, 2]);
+        assert_eq!(items.selection(), Some(1));
+        assert!(list(3, None).move_to(0).is_err());
+    }
+
+    #[test]
+    fn retain_removing_the_selected_item() {
+        let mut items = list(5, Some(2));
+        assert_eq!(items.retain(|i| i % 2 == 1), [0, 2, 4]);
+        assert_eq!(items.items, [1, 3]);
+        // The next remaining item
+        assert_eq!(items.selected(), Some(&3));
+        let mut last = list(3, Some(2));
+        last.retain(|i| *i != 2);
+        assert_eq!(last.selected(), Some(&1));
+    }
+
+    #[test]
+    fn retain_removing_none() {
+        let mut items = list(3, Some(1));
+        assert!(items.retain(|_| true).is_empty());
+        assert_eq!(items.items, [0, 1, 2]);
+        assert_eq!(items.selection(), Some(1));
+    }
+
+    #[test]
+    fn retain_removing_all() {
+        let mut items = list(3, Some(1));
+        assert_eq!(items.retain(|_| false), [0, 1, 2]);
+        assert!(items.is_empty());
+        assert_eq!(items.selection(), None);
+    }
+
+    #[test]
+    fn shift_with_fewer_than_two_items() {
+        let mut single = list(1, Some(0));
+        assert_eq!(single.shift_next().unwrap(), 0);
+        assert_eq!(single.shift_prev().unwrap(), 0);
+        assert_eq!(single.items, [0]);
+        let mut empty = list(0, None);
+        assert!(empty.shift_next().is_err());
+        assert!(empty.shift_prev().is_err());
+    }
+
+    #[test]
+    fn shift_wraps_around_at_the_ends() {
+        let mut first = list(3, Some(0));
+        assert_eq!(first.shift_prev().unwrap(), 2);
+        assert_eq!(first.items, [1, 2, 0]);
+        let mut last = list(3, Some(2));
+        assert_eq!(last.shift_next().unwrap(), 0);
+        assert_eq!(last.items, [2, 0, 1]);
+        let mut middle = list(3, Some(1));
+        assert_eq!(middle.shift_next().unwrap(), 2);
+        assert_eq!(middle.items, [0, 2, 1]);
+    }
+
+    #[test]
+    fn step_wraps_or_stops_at_the_ends() {
+        let mut wrapping = list(3, Some(2));
+        wrapping.step_next(true);
+        assert_eq!(wrapping.selection(), Some(0));
+        wrapping.step_prev(true);
+        assert_eq!(wrapping.selection(), Some(2));
+        let mut clamped = list(3, Some(2));
+        clamped.step_next(false);
+        assert_eq!(clamped.selection(), Some(2));
+        clamped.select(0).unwrap();
+        clamped.step_prev(false);
+        assert_eq!(clamped.selection(), Some(0));
+        clamped.step_next(false);
+        assert_eq!(clamped.selection(), Some(1));
+        // Without a selection, stepping selects an end either way
+        let mut unselected = list(3, None);
+        unselected.step_next(false);
+        assert_eq!(unselected.selection(), Some(0));
+    }
+}
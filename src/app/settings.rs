@@ -0,0 +1,495 @@
+use super::data::{parse_timestamp, Error, FeedbackKind, Result, Task};
+use crate::crypto::Algorithm;
+use crate::ui::styles;
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, fs, io::ErrorKind, path::Path, time::Duration};
+use tui::style::{Color, Style};
+
+pub const SETTINGS_FILENAME: &str = "settings.json";
+/// Settings that could not be loaded are moved here, rather than saved over
+pub const SETTINGS_BACKUP_FILENAME: &str = "settings.json.bak";
+
+pub const BULLETS_UNICODE: (char, char) = ('•', '►');
+pub const BULLETS_ASCII: (char, char) = ('*', '>');
+pub const BULLET_CHILD_UNICODE: char = '◦';
+pub const BULLET_CHILD_ASCII: char = '-';
+
+/// A piece of information shown in the status bar
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StatusSegment {
+    /// Name of the journal file
+    Filename,
+    /// Journal, project and subproject names, shortened to fit
+    Path,
+    /// Terminal size
+    Size,
+    /// Time of day, if `show_clock`
+    Clock,
+    /// Whether there are unsaved changes
+    Dirty,
+    ReadOnly,
+    /// Task filter of the project
+    Filter,
+}
+
+/// Style of feedback messages in the status bar, by kind
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FeedbackStyles {
+    pub nominal: Style,
+    pub warning: Style,
+    pub error: Style,
+    pub critical: Style,
+}
+
+impl Default for FeedbackStyles {
+    fn default() -> Self {
+        Self {
+            nominal: styles::text_good(),
+            warning: styles::text_caution(),
+            error: styles::text_warning(),
+            critical: styles::text_critical(),
+        }
+    }
+}
+
+impl FeedbackStyles {
+    pub fn get(&self, kind: &FeedbackKind) -> Style {
+        match kind {
+            FeedbackKind::Nominal => self.nominal,
+            FeedbackKind::Warning => self.warning,
+            FeedbackKind::Error => self.error,
+            FeedbackKind::Critical => self.critical,
+        }
+    }
+}
+
+/// Whether a key is the one named in the settings: a single character, "Enter" or "Esc"
+fn key_name_matches(name: &str, code: KeyCode) -> bool {
+    match (name, code) {
+        ("Enter", KeyCode::Enter) | ("Esc", KeyCode::Esc) => true,
+        (name, KeyCode::Char(c)) => {
+            let mut chars = name.chars();
+            chars.next() == Some(c) && chars.next().is_none()
+        }
+        _ => false,
+    }
+}
+
+/// Program that opens a file, directory or URL with its default application
+pub fn platform_opener() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Bullet point for list items
+    pub bullet: char,
+    /// Bullet point for the selected list item
+    pub bullet_selected: char,
+    /// Bullet point for nested list items
+    pub bullet_child: char,
+    /// Render masked passwords as one dot per character instead of a solid block
+    pub password_dots: bool,
+    /// Minutes without input before locking the journal
+    pub auto_lock_minutes: Option<u64>,
+    /// Seconds without input before saving changes automatically
+    pub autosave_seconds: Option<u64>,
+    /// Also autosave journals that are not encrypted with a password
+    pub autosave_unencrypted: bool,
+    /// Send a desktop notification when a task becomes due
+    pub notifications: bool,
+    /// Command to open the data directory with, instead of the platform's file manager
+    pub file_manager: Option<String>,
+    /// Encryption of saved files, files are read with the algorithm they were saved with
+    pub encryption: Algorithm,
+    /// Show the time of day in the status bar
+    pub show_clock: bool,
+    /// Left-aligned status bar segments, in order
+    pub status_left: Vec<StatusSegment>,
+    /// Right-aligned status bar segments, in order
+    pub status_right: Vec<StatusSegment>,
+    /// Style of feedback messages, by kind
+    pub feedback_styles: FeedbackStyles,
+    /// Show a completion bar in the border of each subproject
+    pub show_progress: bool,
+    /// Show the most relevant keys above the status bar
+    pub show_hints: bool,
+    /// Rename tasks in place rather than in a centered prompt
+    pub inline_rename: bool,
+    /// Display format of task timestamps (see chrono's strftime)
+    pub timestamp_format: String,
+    /// Display task timestamps as time elapsed, e.g. "2h ago"
+    pub relative_timestamps: bool,
+    /// Days after which an open task is highlighted as stale
+    pub stale_after_days: Option<i64>,
+    /// Color of stale tasks
+    pub stale_color: Color,
+    /// Percent of width the focused subproject grows or shrinks by with `=` and `-`
+    pub focus_width_step: u16,
+    /// Lower bound of the focused subproject width percent
+    pub focus_width_min: u16,
+    /// Upper bound of the focused subproject width percent
+    pub focus_width_max: u16,
+    /// Also write a plaintext Markdown copy of the journal when saving, for diffing
+    pub plaintext_mirror: bool,
+    /// Navigating past the last item selects the first, and vice versa
+    pub wrap_navigation: bool,
+    /// Ask before quitting with unsaved changes
+    pub confirm_quit: bool,
+    /// Keys that answer yes to a confirmation: a character, "Enter" or "Esc"
+    pub confirm_accept_keys: Vec<String>,
+    /// Keys that answer no to a confirmation: a character, "Enter" or "Esc"
+    pub confirm_reject_keys: Vec<String>,
+    /// Open the last used journal when started without a journal name
+    pub reopen_last: bool,
+    /// File name of the last loaded or saved journal, if `reopen_last`
+    pub last_journal: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bullet: BULLETS_UNICODE.0,
+            bullet_selected: BULLETS_UNICODE.1,
+            bullet_child: BULLET_CHILD_UNICODE,
+            password_dots: false,
+            auto_lock_minutes: None,
+            autosave_seconds: None,
+            autosave_unencrypted: false,
+            notifications: false,
+            file_manager: None,
+            encryption: Algorithm::default(),
+            show_clock: true,
+            status_left: vec![StatusSegment::Filename, StatusSegment::Path],
+            status_right: vec![
+                StatusSegment::ReadOnly,
+                StatusSegment::Filter,
+                StatusSegment::Size,
+                StatusSegment::Clock,
+            ],
+            feedback_styles: FeedbackStyles::default(),
+            show_progress: true,
+            show_hints: true,
+            inline_rename: true,
+            timestamp_format: "%Y-%m-%d %H:%M".to_owned(),
+            relative_timestamps: false,
+            stale_after_days: Some(7),
+            stale_color: Color::Rgb(224, 160, 0),
+            focus_width_step: 5,
+            focus_width_min: 5,
+            focus_width_max: 95,
+            plaintext_mirror: false,
+            wrap_navigation: true,
+            confirm_quit: true,
+            confirm_accept_keys: vec!["y".to_owned(), "Y".to_owned()],
+            confirm_reject_keys: vec!["n".to_owned(), "N".to_owned()],
+            reopen_last: true,
+            last_journal: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Load from the data directory, missing fields (or file) take their default
+    pub fn load(datadir: &Path) -> Result<Self> {
+        match fs::read_to_string(datadir.join(SETTINGS_FILENAME)) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(Error::from(e)),
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+        }
+    }
+
+    /// Correct values that cannot be used together, returning a warning for each correction
+    pub fn normalize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (name, value) in [
+            ("focus_width_min", &mut self.focus_width_min),
+            ("focus_width_max", &mut self.focus_width_max),
+        ] {
+            if *value > 100 {
+                warnings.push(format!("{name} is over 100 percent ({value}), using 100"));
+                *value = 100;
+            }
+        }
+        if self.focus_width_min > self.focus_width_max {
+            warnings.push(format!(
+                "focus_width_min ({}) is above focus_width_max ({}), swapping them",
+                self.focus_width_min, self.focus_width_max
+            ));
+            std::mem::swap(&mut self.focus_width_min, &mut self.focus_width_max);
+        }
+        warnings
+    }
+
+    /// Move the settings file aside, returning the name it was moved to
+    pub fn back_up(datadir: &Path) -> Result<&'static str> {
+        fs::rename(
+            datadir.join(SETTINGS_FILENAME),
+            datadir.join(SETTINGS_BACKUP_FILENAME),
+        )?;
+        Ok(SETTINGS_BACKUP_FILENAME)
+    }
+
+    pub fn save(&self, datadir: &Path) -> Result<()> {
+        fs::write(
+            datadir.join(SETTINGS_FILENAME),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// The last used journal to reopen, if enabled and it still exists
+    pub fn last_journal(&self, datadir: &Path) -> Option<String> {
+        self.last_journal
+            .clone()
+            .filter(|name| self.reopen_last && datadir.join(name).is_file())
+    }
+
+    pub fn remember_journal(&mut self, name: String) {
+        self.last_journal = self.reopen_last.then_some(name);
+    }
+
+    /// Program and arguments that open a directory, which is appended as the last argument
+    pub fn file_manager_command(&self) -> Vec<&str> {
+        match &self.file_manager {
+            Some(command) if !command.trim().is_empty() => command.split_whitespace().collect(),
+            _ => vec![platform_opener()],
+        }
+    }
+
+    /// The answer a key gives to a confirmation, if it is one of the configured keys
+    pub fn confirm_answer(&self, code: KeyCode) -> Option<bool> {
+        let matches = |names: &[String]| names.iter().any(|name| key_name_matches(name, code));
+        if matches(&self.confirm_accept_keys) {
+            Some(true)
+        } else if matches(&self.confirm_reject_keys) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Hint for answering a confirmation with the configured keys
+    pub fn confirm_hint(&self) -> String {
+        let mut hint = format!(
+            "{}:yes  {}:no",
+            self.confirm_accept_keys.join("/"),
+            self.confirm_reject_keys.join("/")
+        );
+        // Unless configured as an answer, Esc cancels
+        if self.confirm_answer(KeyCode::Esc).is_none() {
+            hint.push_str("  Esc:cancel");
+        }
+        hint
+    }
+
+    pub fn auto_lock_after(&self) -> Option<Duration> {
+        self.auto_lock_minutes
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+
+    pub fn autosave_after(&self) -> Option<Duration> {
+        self.autosave_seconds.map(Duration::from_secs)
+    }
+
+    /// Format a stored timestamp for display, left as is if it cannot be parsed
+    pub fn format_timestamp(&self, timestamp: &str, now: DateTime<Local>) -> String {
+        let Some(time) = parse_timestamp(timestamp) else {
+            return timestamp.to_owned();
+        };
+        if !self.relative_timestamps {
+            // An invalid format string fails when written rather than when parsed
+            let mut formatted = String::new();
+            return match write!(formatted, "{}", time.format(&self.timestamp_format)) {
+                Ok(_) => formatted,
+                Err(_) => timestamp.to_owned(),
+            };
+        }
+        let elapsed = now.signed_duration_since(time);
+        match elapsed.num_minutes() {
+            ..=0 => "just now".to_owned(),
+            minutes @ 1..=59 => format!("{minutes}m ago"),
+            _ if elapsed.num_hours() < 24 => format!("{}h ago", elapsed.num_hours()),
+            _ => format!("{}d ago", elapsed.num_days()),
+        }
+    }
+
+    pub fn is_stale(&self, task: &Task, now: DateTime<Local>) -> bool {
+        match (self.stale_after_days, parse_timestamp(&task.created_at)) {
+            (Some(days), Some(created_at)) => {
+                !task.is_completed() && now.signed_duration_since(created_at).num_days() >= days
+            }
+            _ => false,
+        }
+    }
+
+    pub fn bullets(&self) -> (char, char) {
+        (self.bullet, self.bullet_selected)
+    }
+
+    pub fn toggle_ascii_bullets(&mut self) {
+        (self.bullet, self.bullet_selected, self.bullet_child) =
+            match self.bullets() == BULLETS_ASCII {
+                true => (BULLETS_UNICODE.0, BULLETS_UNICODE.1, BULLET_CHILD_UNICODE),
+                false => (BULLETS_ASCII.0, BULLETS_ASCII.1, BULLET_CHILD_ASCII),
+            };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    #[test]
+    fn answers_confirmation_with_configured_keys() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.confirm_answer(KeyCode::Char('Y')), Some(true));
+        assert_eq!(settings.confirm_answer(KeyCode::Char('n')), Some(false));
+        assert_eq!(settings.confirm_answer(KeyCode::Char('x')), None);
+        assert_eq!(settings.confirm_hint(), "y/Y:yes  n/N:no  Esc:cancel");
+        settings.confirm_accept_keys = vec!["Enter".to_owned(), "j".to_owned()];
+        settings.confirm_reject_keys = vec!["Esc".to_owned()];
+        assert_eq!(settings.confirm_answer(KeyCode::Enter), Some(true));
+        assert_eq!(settings.confirm_answer(KeyCode::Char('j')), Some(true));
+        assert_eq!(settings.confirm_answer(KeyCode::Char('y')), None);
+        assert_eq!(settings.confirm_answer(KeyCode::Esc), Some(false));
+        assert_eq!(settings.confirm_hint(), "Enter/j:yes  Esc:no");
+    }
+
+    #[test]
+    fn normalizes_focus_widths() {
+        let mut settings = Settings::default();
+        assert!(settings.normalize().is_empty());
+        settings.focus_width_min = 120;
+        settings.focus_width_max = 50;
+        assert_eq!(settings.normalize().len(), 2);
+        assert_eq!(
+            (settings.focus_width_min, settings.focus_width_max),
+            (50, 100)
+        );
+        settings.focus_width_min = 80;
+        settings.focus_width_max = 20;
+        assert_eq!(settings.normalize().len(), 1);
+        assert_eq!(
+            (settings.focus_width_min, settings.focus_width_max),
+            (20, 80)
+        );
+    }
+
+    #[test]
+    fn backs_up_unreadable_settings() {
+        let datadir = scratch_dir("settings_backup");
+        fs::write(datadir.join(SETTINGS_FILENAME), "{ not json").unwrap();
+        assert!(Settings::load(&datadir).is_err());
+        assert_eq!(
+            Settings::back_up(&datadir).unwrap(),
+            SETTINGS_BACKUP_FILENAME
+        );
+        let backup = fs::read_to_string(datadir.join(SETTINGS_BACKUP_FILENAME)).unwrap();
+        assert_eq!(backup, "{ not json");
+        assert!(Settings::load(&datadir).unwrap() == Settings::default());
+    }
+
+    #[test]
+    fn formats_timestamps_absolute_and_relative() {
+        let now = parse_timestamp("2023-05-10 12:00:00").unwrap();
+        let mut settings = Settings::default();
+        let format = |settings: &Settings, timestamp| settings.format_timestamp(timestamp, now);
+        assert_eq!(format(&settings, "2023-05-09 08:30:00"), "2023-05-09 08:30");
+        settings.timestamp_format = "%d/%m".to_owned();
+        assert_eq!(format(&settings, "2023-05-09 08:30:00"), "09/05");
+        settings.timestamp_format = "%Q".to_owned();
+        assert_eq!(
+            format(&settings, "2023-05-09 08:30:00"),
+            "2023-05-09 08:30:00"
+        );
+        assert_eq!(format(&settings, "not a timestamp"), "not a timestamp");
+        settings.relative_timestamps = true;
+        assert_eq!(format(&settings, "2023-05-10 11:59:30"), "just now");
+        assert_eq!(format(&settings, "2023-05-10 11:15:00"), "45m ago");
+        assert_eq!(format(&settings, "2023-05-10 09:00:00"), "3h ago");
+        assert_eq!(format(&settings, "2023-05-07 12:00:00"), "3d ago");
+    }
+
+    #[test]
+    fn tasks_become_stale_at_the_threshold() {
+        let now = parse_timestamp("2023-05-10 12:00:00").unwrap();
+        let settings = Settings::default();
+        let task = |created_at: &str| Task {
+            created_at: created_at.to_owned(),
+            ..Task::new("Task")
+        };
+        assert!(!settings.is_stale(&task("2023-05-03 12:00:01"), now));
+        assert!(settings.is_stale(&task("2023-05-03 12:00:00"), now));
+        let mut completed = task("2023-01-01 00:00:00");
+        completed.toggle_completed();
+        assert!(!settings.is_stale(&completed, now));
+        let settings = Settings {
+            stale_after_days: None,
+            ..Settings::default()
+        };
+        assert!(!settings.is_stale(&task("2023-01-01 00:00:00"), now));
+    }
+
+    #[test]
+    fn reopens_last_journal_only_if_it_exists() {
+        let datadir = scratch_dir("last_journal");
+        let mut settings = Settings::default();
+        assert_eq!(settings.last_journal(&datadir), None);
+        settings.remember_journal("work".to_owned());
+        assert_eq!(settings.last_journal(&datadir), None);
+        fs::write(datadir.join("work"), "data").unwrap();
+        assert_eq!(settings.last_journal(&datadir), Some("work".to_owned()));
+        // Not remembered, nor reopened, when disabled
+        settings.reopen_last = false;
+        assert_eq!(settings.last_journal(&datadir), None);
+        settings.remember_journal("home".to_owned());
+        assert_eq!(settings.last_journal, None);
+    }
+
+    #[test]
+    fn chooses_file_manager_command() {
+        let expected = match () {
+            _ if cfg!(target_os = "macos") => "open",
+            _ if cfg!(target_os = "windows") => "explorer",
+            _ => "xdg-open",
+        };
+        let mut settings = Settings::default();
+        assert_eq!(settings.file_manager_command(), [expected]);
+        settings.file_manager = Some("   ".to_owned());
+        assert_eq!(settings.file_manager_command(), [expected]);
+        settings.file_manager = Some("thunar --new-window".to_owned());
+        assert_eq!(settings.file_manager_command(), ["thunar", "--new-window"]);
+    }
+
+    #[test]
+    fn themes_feedback_by_kind() {
+        let datadir = scratch_dir("feedback_styles");
+        let custom = Style::default().fg(Color::Blue);
+        let error = serde_json::to_value(custom).unwrap();
+        let text = serde_json::json!({ "feedback_styles": { "error": error } }).to_string();
+        fs::write(datadir.join(SETTINGS_FILENAME), text).unwrap();
+        let styles = Settings::load(&datadir).unwrap().feedback_styles;
+        assert_eq!(styles.get(&FeedbackKind::Error), custom);
+        // Kinds left out keep their default
+        let defaults = FeedbackStyles::default();
+        for kind in [
+            FeedbackKind::Nominal,
+            FeedbackKind::Warning,
+            FeedbackKind::Critical,
+        ] {
+            assert_eq!(styles.get(&kind), defaults.get(&kind));
+        }
+    }
+}
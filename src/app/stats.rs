@@ -0,0 +1,193 @@
+use super::data::{parse_timestamp, Journal, Task};
+use chrono::{Duration, NaiveDate};
+
+/// Number of days shown in the activity overlay
+pub const ACTIVITY_DAYS: usize = 28;
+
+/// Summary of the contents of a journal
+#[derive(Default)]
+pub struct JournalStats {
+    pub projects: usize,
+    pub subprojects: usize,
+    pub tasks: usize,
+    pub completed: usize,
+    pub oldest: Option<Task>,
+    pub newest: Option<Task>,
+}
+
+impl JournalStats {
+    pub fn open(&self) -> usize {
+        self.tasks - self.completed
+    }
+
+    pub fn completion_percent(&self) -> f32 {
+        match self.tasks {
+            0 => 0.,
+            tasks => self.completed as f32 / tasks as f32 * 100.,
+        }
+    }
+}
+
+impl From<&Journal<'_>> for JournalStats {
+    fn from(journal: &Journal) -> Self {
+        let mut stats = JournalStats {
+            projects: journal.projects.len(),
+            ..Default::default()
+        };
+        for project in journal.projects.iter() {
+            stats.subprojects += project.subprojects.len();
+            for subproject in project.subprojects.iter() {
+                stats.tasks += subproject.tasks.len();
+                for task in subproject.tasks.iter() {
+                    if task.is_completed() {
+                        stats.completed += 1;
+                    }
+                    // Timestamps are formatted to sort chronologically
+                    if stats
+                        .oldest
+                        .as_ref()
+                        .is_none_or(|t| task.created_at < t.created_at)
+                    {
+                        stats.oldest = Some(task.clone());
+                    }
+                    if stats
+                        .newest
+                        .as_ref()
+                        .is_none_or(|t| task.created_at >= t.created_at)
+                    {
+                        stats.newest = Some(task.clone());
+                    }
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// Tasks created and completed on each day of a range
+pub struct Activity {
+    pub created: Vec<u64>,
+    pub completed: Vec<u64>,
+}
+
+impl Activity {
+    /// Daily counts for the `days` days up to and including `last_day`
+    pub fn new(journal: &Journal, last_day: NaiveDate, days: usize) -> Self {
+        let mut activity = Activity {
+            created: vec![0; days],
+            completed: vec![0; days],
+        };
+        let first_day = last_day - Duration::days(days as i64 - 1);
+        let day_index = |timestamp: &str| {
+            let date = parse_timestamp(timestamp)?.date_naive();
+            let index = date.signed_duration_since(first_day).num_days();
+            usize::try_from(index).ok().filter(|i| *i < days)
+        };
+        for project in journal.projects.iter() {
+            for subproject in project.subprojects.iter() {
+                for task in subproject.tasks.iter() {
+                    if let Some(index) = day_index(&task.created_at) {
+                        activity.created[index] += 1;
+                    }
+                    if let Some(index) = task.completed_at.as_deref().and_then(day_index) {
+                        activity.completed[index] += 1;
+                    }
+                }
+            }
+        }
+        activity
+    }
+
+    /// Created and completed totals of the week ending `weeks_ago` weeks before the last day
+    pub fn week(&self, weeks_ago: usize) -> (u64, u64) {
+        let end = self.created.len().saturating_sub(weeks_ago * 7);
+        let start = end.saturating_sub(7);
+        (
+            self.created[start..end].iter().sum(),
+            self.completed[start..end].iter().sum(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::data::{Project, SubProject};
+
+    fn task(desc: &str, created_at: &str, completed_at: Option<&str>) -> Task {
+        Task {
+            created_at: created_at.to_owned(),
+            completed_at: completed_at.map(str::to_owned),
+            ..Task::new(desc)
+        }
+    }
+
+    /// Two projects, one with two subprojects, with tasks created over a week
+    fn fixture() -> Journal<'static> {
+        let mut work = Project::new("Work");
+        work.subprojects = vec![SubProject::new("Backlog"), SubProject::new("Done")].into();
+        let backlog = work.subprojects.get_item_mut(Some(0)).unwrap();
+        backlog
+            .tasks
+            .push_item(task("Plan", "2023-01-02 10:00:00", None));
+        backlog
+            .tasks
+            .push_item(task("Review", "2023-01-05 12:00:00", None));
+        let done = work.subprojects.get_item_mut(Some(1)).unwrap();
+        done.tasks.push_item(task(
+            "Oldest",
+            "2023-01-01 08:00:00",
+            Some("2023-01-03 09:00:00"),
+        ));
+        let mut home = Project::new("Home");
+        home.subprojects
+            .get_item_mut(Some(0))
+            .unwrap()
+            .tasks
+            .push_item(task(
+                "Newest",
+                "2023-01-07 18:00:00",
+                Some("2023-01-07 19:00:00"),
+            ));
+        let mut journal = Journal::new("Stats");
+        journal.projects = vec![work, home].into();
+        journal
+    }
+
+    #[test]
+    fn summarizes_journal() {
+        let stats = JournalStats::from(&fixture());
+        assert_eq!(stats.projects, 2);
+        assert_eq!(stats.subprojects, 3);
+        assert_eq!(stats.tasks, 4);
+        assert_eq!(stats.completed, 2);
+        assert_eq!(stats.open(), 2);
+        assert_eq!(stats.completion_percent(), 50.);
+        assert_eq!(stats.oldest.unwrap().desc, "Oldest");
+        assert_eq!(stats.newest.unwrap().desc, "Newest");
+    }
+
+    #[test]
+    fn summarizes_empty_journal() {
+        let mut journal = Journal::new("Empty");
+        journal.projects = Vec::new().into();
+        let stats = JournalStats::from(&journal);
+        assert_eq!(stats.tasks, 0);
+        assert_eq!(stats.completion_percent(), 0.);
+        assert!(stats.oldest.is_none() && stats.newest.is_none());
+    }
+
+    #[test]
+    fn buckets_activity_by_day() {
+        let last_day = NaiveDate::from_ymd_opt(2023, 1, 7).unwrap();
+        let activity = Activity::new(&fixture(), last_day, 7);
+        assert_eq!(activity.created, [1, 1, 0, 0, 1, 0, 1]);
+        assert_eq!(activity.completed, [0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(activity.week(0), (4, 2));
+        // Timestamps outside the range are left out
+        let activity = Activity::new(&fixture(), last_day, 3);
+        assert_eq!(activity.created, [1, 0, 1]);
+        assert_eq!(activity.week(0), (2, 1));
+        assert_eq!(activity.week(1), (0, 0));
+    }
+}
@@ -0,0 +1,27 @@
+// Text processing shared by matchers
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Lowercase without diacritics, so that e.g. "Café" and "cafe" match each other
+pub fn normalize_for_search(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_case_and_accents() {
+        assert_eq!(normalize_for_search("Café"), "cafe");
+        assert_eq!(normalize_for_search("CAFE\u{301}"), "cafe");
+        assert_eq!(normalize_for_search("Ångström Über"), "angstrom uber");
+        assert_eq!(normalize_for_search("plain text"), "plain text");
+        assert_eq!(
+            normalize_for_search("Crème Brûlée"),
+            normalize_for_search("creme brulee")
+        );
+    }
+}
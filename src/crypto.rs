@@ -1,12 +1,23 @@
-use crate::app::data::{Error, Result};
+use crate::app::data::{Error, LoadError, Result};
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     aes::cipher::InvalidLength,
     Aes256Gcm, Nonce,
 };
+use chacha20poly1305::ChaCha20Poly1305;
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::Deref};
+use zeroize::{Zeroize, Zeroizing};
 
 const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+/// Start of encrypted data, followed by the format version of the plaintext and the algorithm id.
+/// Data without it is from before the header was added: format version 0, encrypted with
+/// AES-256-GCM.
+const MAGIC: &[u8] = b"DEVJOURNAL";
+const HEADER_SIZE: usize = MAGIC.len() + 2;
 
 impl From<InvalidLength> for Error {
     fn from(_: InvalidLength) -> Self {
@@ -20,33 +31,229 @@ impl From<aes_gcm::Error> for Error {
     }
 }
 
-fn get_cipher(key: &str) -> Result<Aes256Gcm> {
-    let key = key.as_bytes().to_vec();
-    let mut fixed_key: Vec<u8> = vec![0; 32];
-    fixed_key.splice(0..key.len(), key);
-    let cipher = Aes256Gcm::new_from_slice(fixed_key.as_slice())?;
-    Ok(cipher)
+/// A password, scrubbed from memory when dropped and redacted from debug output
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Password(String);
+
+impl Zeroize for Password {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for Password {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Deref for Password {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(***)")
+    }
 }
 
-pub fn encrypt(plaintext: &Vec<u8>, key: &str) -> Result<Vec<u8>> {
-    let cipher = get_cipher(key)?;
+impl From<String> for Password {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Password {
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+/// Authenticated encryption algorithm, recorded in the header of encrypted data
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    fn id(self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 1,
+            Self::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> std::result::Result<Self, LoadError> {
+        match id {
+            1 => Ok(Self::Aes256Gcm),
+            2 => Ok(Self::ChaCha20Poly1305),
+            _ => Err(LoadError::Unsupported(format!(
+                "encryption algorithm [{id}]"
+            ))),
+        }
+    }
+
+    /// Encrypt, authenticating the header (`aad`) along with the plaintext
+    fn encrypt(self, key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg, aad };
+        let ciphertext = match self {
+            Self::Aes256Gcm => Aes256Gcm::new_from_slice(key)?.encrypt(nonce, payload),
+            Self::ChaCha20Poly1305 => {
+                ChaCha20Poly1305::new_from_slice(key)?.encrypt(nonce, payload)
+            }
+        };
+        ciphertext.map_err(|e| Error::from(format!("encryption failure [{e}]")))
+    }
+
+    fn decrypt(self, key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg, aad };
+        let plaintext = match self {
+            Self::Aes256Gcm => Aes256Gcm::new_from_slice(key)?.decrypt(nonce, payload),
+            Self::ChaCha20Poly1305 => {
+                ChaCha20Poly1305::new_from_slice(key)?.decrypt(nonce, payload)
+            }
+        };
+        plaintext.map_err(|e| Error::from(format!("decryption failure [{e}]")))
+    }
+}
+
+/// The password as a key: zero padded, or cut to the key size if longer
+fn fixed_key(key: &str) -> Zeroizing<[u8; KEY_SIZE]> {
+    let key = key.as_bytes();
+    let len = key.len().min(KEY_SIZE);
+    let mut fixed_key = Zeroizing::new([0; KEY_SIZE]);
+    fixed_key[..len].copy_from_slice(&key[..len]);
+    fixed_key
+}
+
+/// Encrypt plaintext of the given format version, which is recorded in the header
+pub fn encrypt(plaintext: &[u8], key: &str, algorithm: Algorithm, version: u8) -> Result<Vec<u8>> {
     let nonce_data: [u8; NONCE_SIZE] = thread_rng().gen();
-    let mut ciphertext = cipher
-        .encrypt(Nonce::from_slice(&nonce_data), plaintext.as_slice())
-        .map_err(|e| Error::from(format!("encryption failure [{e}]")))?;
-    ciphertext.extend_from_slice(&nonce_data);
-    Ok(ciphertext)
+    let mut data = MAGIC.to_vec();
+    data.extend([version, algorithm.id()]);
+    let ciphertext = algorithm.encrypt(fixed_key(key).as_slice(), &nonce_data, plaintext, &data)?;
+    data.extend(ciphertext);
+    data.extend_from_slice(&nonce_data);
+    Ok(data)
+}
+
+/// Whether the data could have been produced by `encrypt`: with its header, or long enough for
+/// the nonce and authentication tag and not text (which ciphertext practically never is)
+pub fn is_ciphertext(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+        || (data.len() > NONCE_SIZE + TAG_SIZE && std::str::from_utf8(data).is_err())
 }
 
-pub fn decrypt(ciphertext: &Vec<u8>, key: &str) -> Result<Vec<u8>> {
-    let cipher = get_cipher(key)?;
+/// Decrypt, returning the format version of the plaintext along with it
+pub fn decrypt(data: &[u8], key: &str) -> std::result::Result<(u8, Vec<u8>), LoadError> {
+    let too_small = || LoadError::Corrupt(Error::from("corrupted file [too small]"));
+    let (version, algorithm, header, ciphertext) = match data.strip_prefix(MAGIC) {
+        Some([version, id, ciphertext @ ..]) => (
+            *version,
+            Algorithm::from_id(*id)?,
+            &data[..HEADER_SIZE],
+            ciphertext,
+        ),
+        Some(_) => return Err(too_small()),
+        None => (0, Algorithm::Aes256Gcm, &[][..], data),
+    };
     let split_at = ciphertext.len().saturating_sub(NONCE_SIZE);
-    (split_at > 0)
-        .then_some(())
-        .ok_or(Error::from("corrupted file [too small]"))?;
+    (split_at > 0).then_some(()).ok_or_else(too_small)?;
     let (ciphertext, nonce_data) = ciphertext.split_at(split_at);
-    let plaintext = cipher
-        .decrypt(Nonce::from_slice(nonce_data), ciphertext)
-        .map_err(|e| Error::from(format!("decryption failure [{e}]")))?;
-    Ok(plaintext)
+    let plaintext = algorithm
+        .decrypt(fixed_key(key).as_slice(), nonce_data, ciphertext, header)
+        .map_err(|_| LoadError::Decrypt)?;
+    Ok((version, plaintext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_with_each_algorithm() {
+        for algorithm in [Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+            let data = encrypt(b"plaintext", "password", algorithm, 7).unwrap();
+            assert!(is_ciphertext(&data));
+            assert_eq!(
+                decrypt(&data, "password").unwrap(),
+                (7, b"plaintext".to_vec())
+            );
+            assert!(matches!(decrypt(&data, "other"), Err(LoadError::Decrypt)));
+        }
+    }
+
+    #[test]
+    fn authenticates_header() {
+        let mut data = encrypt(b"plaintext", "", Algorithm::default(), 1).unwrap();
+        data[MAGIC.len()] = 0;
+        assert!(matches!(decrypt(&data, ""), Err(LoadError::Decrypt)));
+    }
+
+    #[test]
+    fn pads_and_cuts_passwords_to_key_size() {
+        assert_eq!(fixed_key("ab")[..3], [b'a', b'b', 0]);
+        let long = "x".repeat(KEY_SIZE * 2);
+        assert_eq!(*fixed_key(&long), [b'x'; KEY_SIZE]);
+        let data = encrypt(b"plaintext", &long, Algorithm::default(), 1).unwrap();
+        assert_eq!(decrypt(&data, &long).unwrap().1, b"plaintext");
+    }
+
+    #[test]
+    fn recognizes_ciphertext() {
+        assert!(is_ciphertext(b"DEVJOURNAL"));
+        assert!(!is_ciphertext(
+            b"a plain text file, long enough to hold a nonce and tag"
+        ));
+        assert!(!is_ciphertext(&[0xff; NONCE_SIZE + TAG_SIZE]));
+        assert!(is_ciphertext(&[0xff; NONCE_SIZE + TAG_SIZE + 1]));
+    }
+
+    #[test]
+    fn header_selects_algorithm() {
+        for algorithm in [Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+            let data = encrypt(b"plaintext", "", algorithm, 1).unwrap();
+            assert_eq!(data[MAGIC.len() + 1], algorithm.id());
+            // Decrypting the ciphertext as the other algorithm fails
+            let other = match algorithm {
+                Algorithm::Aes256Gcm => Algorithm::ChaCha20Poly1305,
+                Algorithm::ChaCha20Poly1305 => Algorithm::Aes256Gcm,
+            };
+            let (header, rest) = data.split_at(HEADER_SIZE);
+            let (ciphertext, nonce) = rest.split_at(rest.len() - NONCE_SIZE);
+            let key = fixed_key("");
+            assert!(algorithm
+                .decrypt(key.as_slice(), nonce, ciphertext, header)
+                .is_ok());
+            assert!(other
+                .decrypt(key.as_slice(), nonce, ciphertext, header)
+                .is_err());
+        }
+        let mut data = encrypt(b"plaintext", "", Algorithm::default(), 1).unwrap();
+        data[MAGIC.len() + 1] = 9;
+        assert!(matches!(decrypt(&data, ""), Err(LoadError::Unsupported(_))));
+    }
+
+    #[test]
+    fn scrubs_and_redacts_password() {
+        let mut password = Password::from("open sesame");
+        assert_eq!(format!("{password:?}"), "Password(***)");
+        let (ptr, capacity) = (password.0.as_ptr(), password.0.capacity());
+        // As done on drop, after which the buffer can no longer be inspected
+        password.zeroize();
+        assert!(password.is_empty());
+        assert_eq!(password.0.as_ptr(), ptr);
+        // SAFETY: the buffer is still allocated, and zeroize initialized all of it
+        let buffer = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+        assert!(buffer.iter().all(|byte| *byte == 0));
+    }
 }
@@ -1,14 +1,20 @@
 /// Main entry point
 mod app;
 mod crypto;
+#[cfg(test)]
+mod testing;
 mod ui;
 use app::run_app;
 use clap::Parser;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{error::Error, io};
+use std::{
+    error::Error,
+    io::{self, IsTerminal},
+    process::ExitCode,
+};
 use tui::{backend::CrosstermBackend, Terminal};
 
 #[derive(Parser, Debug)]
@@ -16,33 +22,64 @@ use tui::{backend::CrosstermBackend, Terminal};
 struct Args {
     #[arg(default_value_t = String::from(""))]
     journal_name: String,
+    /// Lock the journal after this many minutes without input
+    #[arg(long, value_name = "MINUTES")]
+    auto_lock: Option<u64>,
+    /// Open the journal for viewing only, ignoring keys that would change it
+    #[arg(long)]
+    read_only: bool,
+    /// Select a task on opening, as "project/subproject/task" (trailing segments may be omitted)
+    #[arg(long, value_name = "PATH")]
+    goto: Option<String>,
 }
 
-pub fn main() -> Result<(), Box<dyn Error>> {
+pub fn main() -> Result<ExitCode, Box<dyn Error>> {
     let args = Args::parse();
     let target_name = match args.journal_name.as_str() {
         "" => None,
         s => Some(s.to_owned()),
     };
+    // Without a terminal to prompt on, fail early if the journal cannot be opened
+    if let Some(name) = &target_name {
+        if !io::stdin().is_terminal() {
+            if let Err(err) = app::check_journal(name, args.goto.as_deref()) {
+                eprintln!("{err}");
+                return Ok(ExitCode::from(err.exit_code()));
+            }
+        }
+    }
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    crossterm::execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     // create and run the app
-    let res = run_app(&mut terminal, target_name);
+    let res = run_app(
+        &mut terminal,
+        target_name,
+        args.auto_lock,
+        args.read_only,
+        args.goto,
+    );
     // restore terminal
     disable_raw_mode()?;
     crossterm::execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     // Send errors to stderr
     if let Err(err) = res {
-        eprintln!("{err}")
+        eprintln!("{err}");
+        return Ok(ExitCode::FAILURE);
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
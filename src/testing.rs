@@ -0,0 +1,10 @@
+// Helpers shared by tests
+use std::{fs, path::PathBuf};
+
+/// An empty directory unique to the test, replacing any left over from a previous run
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("devjournal-test-{}-{name}", std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -1,48 +1,159 @@
-use crate::app::data::{filename, App, FeedbackKind, Project};
+use crate::app::{
+    data::{
+        filename, parse_timestamp, App, FocusRegion, JournalPrompt, Overlay, Project, Task,
+        PRIORITY_LABELS,
+    },
+    list::SelectionList,
+    settings::{Settings, StatusSegment},
+    stats::{Activity, JournalStats, ACTIVITY_DAYS},
+};
 pub mod events;
-mod styles;
+mod markdown;
+pub mod styles;
 pub mod widgets;
-use self::widgets::{center_rect, list::ListWidget};
+use self::widgets::{
+    center_rect,
+    list::{row_at, selected_row, ListWidget},
+    truncate_to_width,
+};
+use chrono::{DateTime, Local};
+use std::borrow::Cow;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Tabs},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Sparkline, Table, Tabs, Wrap},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
-pub fn draw<B: Backend>(frame: &mut Frame<B>, state: &App, debug: bool) {
-    let chunks = Layout::default()
+/// Tab bar, main area, hint line and status bar
+pub fn layout(size: Rect, settings: &Settings) -> Vec<Rect> {
+    let hint_height = settings.show_hints as u16;
+    Layout::default()
         .constraints(vec![
             Constraint::Length(2),
-            Constraint::Length(frame.size().height.saturating_sub(3)),
+            Constraint::Length(size.height.saturating_sub(3 + hint_height)),
+            Constraint::Length(hint_height),
             Constraint::Length(1),
         ])
-        .split(frame.size());
+        .split(size)
+}
+
+pub fn draw<B: Backend>(frame: &mut Frame<B>, state: &App) {
+    let chunks = layout(frame.size(), &state.settings);
+    if state.locked {
+        state.prompt.draw(frame, chunks[1], &state.settings);
+        if state.settings.show_hints {
+            draw_hints(frame, state, chunks[2]);
+        }
+        draw_feedback(frame, state, chunks[3]);
+        return;
+    }
     draw_tab_bar(frame, state, chunks[0]);
-    if debug {
+    if state.debug {
         draw_debug_tab(frame, state, chunks[1]);
     } else {
         if let Some(project) = state.journal.projects.selected() {
-            draw_project(frame, project, chunks[1]);
+            draw_project(frame, project, chunks[1], &state.settings);
+        }
+        match state.overlay {
+            Some(Overlay::TaskInfo) => {
+                if let Some(task) = state.journal.selected_task() {
+                    draw_task_info(
+                        frame,
+                        task,
+                        center_rect(60, 11, chunks[1], 1),
+                        &state.settings,
+                    );
+                }
+            }
+            Some(Overlay::Stats) => draw_stats(frame, state, center_rect(60, 10, chunks[1], 1)),
+            Some(Overlay::Activity) => draw_activity(
+                frame,
+                state,
+                center_rect(ACTIVITY_DAYS as u16 + 2, 8, chunks[1], 1),
+            ),
+            Some(Overlay::Today) => draw_today(frame, state, center_rect(70, 20, chunks[1], 1)),
+            None => (),
         }
         if state.file_request.is_some() {
             state
                 .filelist
-                .draw(frame, center_rect(40, 20, chunks[1], 1));
+                .draw(frame, center_rect(60, 20, chunks[1], 1), &state.settings);
         }
     };
+    if state.choice_request.is_some() {
+        state.choice.draw(frame, chunks[1], &state.settings);
+    }
+    if state.confirm_request.is_some() {
+        state.confirm.draw(frame, chunks[1]);
+    }
     if state.prompt_request.is_some() {
-        state.prompt.draw(frame, chunks[1]);
+        state.prompt.draw(frame, chunks[1], &state.settings);
+    }
+    if state.settings.show_hints {
+        draw_hints(frame, state, chunks[2]);
     }
-    draw_status_bar(frame, state, chunks[2]);
+    draw_status_bar(frame, state, chunks[3]);
+}
+
+/// The most relevant keys for what currently has focus
+fn hint_text(state: &App) -> Cow<'static, str> {
+    let project_prompt = state
+        .journal
+        .projects
+        .selected()
+        .and_then(|p| p.prompt_request.as_ref());
+    let text = if state.locked {
+        "Enter:unlock  Ctrl+q:quit"
+    } else if state.debug {
+        "F12:close debug view  Ctrl+q:quit"
+    } else if state.prompt_request.is_some() {
+        "Enter:confirm  Esc:cancel"
+    } else if state.confirm_request.is_some() {
+        return Cow::Owned(state.settings.confirm_hint());
+    } else if state.choice_request.is_some() {
+        "j/k:move  Enter:choose  1-9:pick  Esc:cancel"
+    } else if matches!(state.overlay, Some(Overlay::Today)) {
+        "j/k:move  Enter:go to task  Esc:close"
+    } else if state.overlay.is_some() {
+        "t:relative time  Esc:close"
+    } else if state.file_request.is_some() {
+        "Enter:select  d:trash  t:view trash  Tab:name prompt  Esc:cancel"
+    } else if project_prompt.is_some_and(|request| request.multiline()) {
+        "Ctrl+s:add  Esc:cancel"
+    } else if project_prompt.is_some() {
+        "Enter:confirm  Esc:cancel"
+    } else if state
+        .journal
+        .projects
+        .selected()
+        .is_some_and(|p| p.filter.is_some())
+    {
+        "n:next match  N:previous match  /:edit filter  Esc:clear filter"
+    } else {
+        "n:new  r:rename  d:delete  Space:done  i:info  f:focus  Tab:project  F3:hide hints"
+    };
+    Cow::Borrowed(text)
+}
+
+fn draw_hints<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
+    let text = truncate_to_width(&hint_text(state), chunk.width as usize);
+    frame.render_widget(
+        Paragraph::new(Span::styled(text, styles::text_dim())),
+        chunk,
+    );
 }
 
 fn draw_tab_bar<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
     let block = Block::default()
         .borders(Borders::BOTTOM)
-        .border_style(styles::border());
+        .border_style(match state.focus {
+            FocusRegion::Tabs => styles::border_highlighted(),
+            _ => styles::border(),
+        });
     let inner = block.inner(chunk);
     frame.render_widget(block, chunk);
     let chunks = Layout::default()
@@ -61,19 +172,61 @@ fn draw_tab_bar<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
         Paragraph::new(Span::styled(title_text, title_style)),
         chunks[1],
     );
+    // Each tab is padded by a space on either side and followed by a divider
+    let widths: Vec<usize> = state
+        .journal
+        .projects
+        .iter()
+        .map(|p| p.name.width() + 3)
+        .collect();
+    let width = chunks[2].width.saturating_sub(1) as usize;
+    let selection = state.journal.projects.selection();
+    let offset = tab_offset(&widths, selection.unwrap_or_default(), width);
     let titles = state
         .journal
         .projects
         .iter()
-        .map(|t| Spans::from(Span::styled(&t.name, styles::tab_dim())))
+        .skip(offset)
+        .map(|p| Spans::from(Span::styled(&p.name, styles::tab_dim().patch(tab_color(p)))))
         .collect();
     let mut tabs = Tabs::new(titles)
         .block(Block::default().borders(Borders::LEFT))
         .highlight_style(styles::tab_dim());
-    if let Some(selected) = state.journal.projects.selection() {
-        tabs = tabs.select(selected).highlight_style(styles::tab());
+    if let (Some(selected), Some(project)) = (selection, state.journal.projects.selected()) {
+        let highlight = styles::tab().patch(tab_color(project));
+        tabs = tabs.select(selected - offset).highlight_style(highlight);
     }
     frame.render_widget(tabs, chunks[2]);
+    let ellipsis = |x: u16| {
+        let rect = Rect::new(x, chunks[2].y, 1, chunks[2].height.min(1));
+        (Paragraph::new(Span::styled("…", styles::tab_dim())), rect)
+    };
+    if offset > 0 && chunks[2].width > 1 {
+        let (widget, rect) = ellipsis(chunks[2].x + 1);
+        frame.render_widget(widget, rect);
+    }
+    if widths[offset..].iter().sum::<usize>() > width + 1 && chunks[2].width > 0 {
+        let (widget, rect) = ellipsis(chunks[2].right() - 1);
+        frame.render_widget(widget, rect);
+    }
+}
+
+/// Index of the first tab to draw such that the selected tab fits within the width, leaving a
+/// column for the overflow indicator
+fn tab_offset(widths: &[usize], selected: usize, width: usize) -> usize {
+    let mut offset = 0;
+    while offset < selected && widths[offset..=selected].iter().sum::<usize>() + 1 > width {
+        offset += 1;
+    }
+    offset
+}
+
+/// Foreground of the project color, if set
+fn tab_color(project: &Project) -> Style {
+    match project.color.as_deref().and_then(styles::label_color) {
+        Some(color) => Style::default().fg(color),
+        None => Style::default(),
+    }
 }
 
 fn draw_status_bar<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
@@ -81,30 +234,96 @@ fn draw_status_bar<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
         .direction(Direction::Horizontal)
         .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(chunk);
-    let mut journal_path = state.journal.name.clone();
+    let size = frame.size();
+    let left = status_spans(&state.settings.status_left, state, size, chunks[0].width);
+    let status_left = Paragraph::new(left).alignment(tui::layout::Alignment::Left);
+    frame.render_widget(status_left, chunks[0]);
+    let right = status_spans(&state.settings.status_right, state, size, chunks[1].width);
+    let status_right = Paragraph::new(right).alignment(tui::layout::Alignment::Right);
+    frame.render_widget(status_right, chunks[1]);
+    draw_feedback(frame, state, chunk);
+}
+
+/// Status bar segments separated by spaces, with the path shortened to fit the width
+fn status_spans(segments: &[StatusSegment], state: &App, size: Rect, width: u16) -> Spans<'static> {
+    let texts: Vec<Option<(String, Style)>> = segments
+        .iter()
+        .map(|segment| match segment {
+            StatusSegment::Filename => {
+                Some((format!("`{}`", filename(&state.filepath)), styles::text()))
+            }
+            StatusSegment::Path => Some((String::new(), styles::text_dim())),
+            StatusSegment::Size => Some((
+                format!("{}×{}", size.width, size.height),
+                styles::text_dim(),
+            )),
+            StatusSegment::Clock => state
+                .settings
+                .show_clock
+                .then(|| (clock_text(&Local::now()), styles::text())),
+            StatusSegment::Dirty => state
+                .is_dirty()
+                .then(|| ("[+]".to_owned(), styles::text_warning())),
+            StatusSegment::ReadOnly => state
+                .read_only
+                .then(|| ("READ ONLY".to_owned(), styles::text_warning())),
+            StatusSegment::Filter => state
+                .journal
+                .projects
+                .selected()
+                .and_then(|p| p.filter.as_ref())
+                .map(|filter| (format!("/{}", filter.pattern()), styles::text())),
+        })
+        .collect();
+    let shown = texts.iter().flatten().count();
+    let used: usize = texts.iter().flatten().map(|(text, _)| text.width()).sum();
+    let path_width = (width as usize).saturating_sub(used + shown.saturating_sub(1) + 2);
+    let mut spans = Vec::new();
+    for (segment, text) in segments.iter().zip(texts) {
+        let Some((mut text, style)) = text else {
+            continue;
+        };
+        if *segment == StatusSegment::Path {
+            text = format!("[{}]", truncate_path(&journal_path(state), path_width));
+        }
+        if !spans.is_empty() {
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::styled(text, style));
+    }
+    Spans::from(spans)
+}
+
+/// Names of the journal and the selected project and subproject
+fn journal_path<'a>(state: &'a App) -> Vec<&'a str> {
+    let mut path = vec![state.journal.name.as_str()];
     if let Some(project) = state.journal.projects.selected() {
-        journal_path += &format!(" / {}", project.name);
+        path.push(&project.name);
         if let Some(subproject) = project.subprojects.selected() {
-            journal_path += &format!(" / {}", subproject.name);
+            path.push(&subproject.name);
         }
     };
-    let spans = Spans::from(vec![
-        Span::styled(format!("`{}`", filename(&state.filepath)), styles::text()),
-        Span::styled(format!(" [{journal_path}]"), styles::text_dim()),
-    ]);
-    let status_filename = Paragraph::new(spans).alignment(tui::layout::Alignment::Left);
-    frame.render_widget(status_filename, chunks[0]);
-    let status_terminal = Paragraph::new(Span::styled(
-        format!("{}×{}", frame.size().width, frame.size().height),
-        styles::text_dim(),
-    ))
-    .alignment(tui::layout::Alignment::Right);
-    frame.render_widget(status_terminal, chunks[1]);
+    path
+}
+
+/// Time since the task was created, in its largest unit, e.g. "2d"
+fn task_age(task: &Task, now: DateTime<Local>) -> Option<String> {
+    let elapsed = now.signed_duration_since(parse_timestamp(&task.created_at)?);
+    Some(match elapsed.num_minutes() {
+        ..=0 => "now".to_owned(),
+        minutes @ 1..=59 => format!("{minutes}m"),
+        _ if elapsed.num_hours() < 24 => format!("{}h", elapsed.num_hours()),
+        _ => format!("{}d", elapsed.num_days()),
+    })
+}
+
+fn clock_text(time: &DateTime<Local>) -> String {
+    time.format("%H:%M").to_string()
+}
+
+fn draw_feedback<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
     if let Some(feedback) = state.feedback() {
-        let style = match feedback.kind {
-            FeedbackKind::Nominal => styles::text_good(),
-            FeedbackKind::Error => styles::text_warning(),
-        };
+        let style = state.settings.feedback_styles.get(&feedback.kind);
         let paragraph = Paragraph::new(format!(" {}", feedback.message.clone()))
             .alignment(tui::layout::Alignment::Center)
             .style(style);
@@ -113,6 +332,21 @@ fn draw_status_bar<B: Backend>(frame: &mut Frame<B>, state: &App, chunk: Rect) {
     };
 }
 
+/// Join path segments, eliding the middle of the path if it exceeds the width so that the leaf
+/// segment remains visible
+fn truncate_path(segments: &[&str], width: usize) -> String {
+    let path = segments.join(" / ");
+    if path.width() <= width || segments.len() < 2 {
+        return truncate_to_width(&path, width);
+    }
+    let tail = format!(" / {}", segments[segments.len() - 1]);
+    let head_width = width.saturating_sub(tail.width());
+    match head_width > 1 {
+        true => format!("{}{tail}", truncate_to_width(&path, head_width)),
+        false => truncate_to_width(tail.trim_start_matches(" / "), width),
+    }
+}
+
 pub fn draw_debug_tab<B>(frame: &mut Frame<B>, _state: &App, area: Rect)
 where
     B: Backend,
@@ -172,25 +406,360 @@ where
     frame.render_widget(table, chunks[0]);
 }
 
-fn draw_project<B: Backend>(frame: &mut Frame<B>, project: &Project, rect: Rect) {
-    draw_subprojects(frame, project, rect);
-    if project.prompt_request.is_some() {
-        project.prompt.draw(frame, rect);
+fn draw_stats<B: Backend>(frame: &mut Frame<B>, state: &App, rect: Rect) {
+    let stats = JournalStats::from(&state.journal);
+    let now = Local::now();
+    let task_summary = |task: &Option<Task>| {
+        task.as_ref().map_or("-".to_owned(), |t| {
+            let created_at = state.settings.format_timestamp(&t.created_at, now);
+            format!("{} ({created_at})", t.desc)
+        })
     };
+    let rows = [
+        ("Projects", stats.projects.to_string()),
+        ("Subprojects", stats.subprojects.to_string()),
+        ("Tasks", stats.tasks.to_string()),
+        (
+            "Completed",
+            format!("{} ({:.0}%)", stats.completed, stats.completion_percent()),
+        ),
+        ("Open", stats.open().to_string()),
+        ("Oldest", task_summary(&stats.oldest)),
+        ("Newest", task_summary(&stats.newest)),
+    ];
+    let items: Vec<Row> = rows
+        .into_iter()
+        .map(|(name, value)| {
+            Row::new(vec![
+                Cell::from(Span::styled(name, styles::text_dim())),
+                Cell::from(Span::styled(value, styles::text())),
+            ])
+        })
+        .collect();
+    let table = Table::new(items)
+        .block(
+            Block::default()
+                .title(Span::styled("Statistics", styles::title()))
+                .borders(Borders::ALL)
+                .border_style(styles::border_highlighted()),
+        )
+        .widths(&[Constraint::Length(12), Constraint::Min(0)]);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(table, rect);
+}
+
+fn draw_activity<B: Backend>(frame: &mut Frame<B>, state: &App, rect: Rect) {
+    let activity = Activity::new(&state.journal, Local::now().date_naive(), ACTIVITY_DAYS);
+    let block = Block::default()
+        .title(Span::styled(
+            format!("Activity ({ACTIVITY_DAYS} days)"),
+            styles::title(),
+        ))
+        .borders(Borders::ALL)
+        .border_style(styles::border_highlighted());
+    let inner = block.inner(rect);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(block, rect);
+    let chunks = Layout::default()
+        .constraints(vec![
+            Constraint::Length(1),
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
+        .split(inner);
+    let (created, completed) = activity.week(0);
+    let (prev_created, prev_completed) = activity.week(1);
+    let label = |text: String| Paragraph::new(Span::styled(text, styles::text_dim()));
+    frame.render_widget(
+        label(format!("Created ({created}, prev week {prev_created})")),
+        chunks[0],
+    );
+    frame.render_widget(
+        Sparkline::default()
+            .data(&activity.created)
+            .style(styles::text()),
+        chunks[1],
+    );
+    frame.render_widget(
+        label(format!(
+            "Completed ({completed}, prev week {prev_completed})"
+        )),
+        chunks[2],
+    );
+    frame.render_widget(
+        Sparkline::default()
+            .data(&activity.completed)
+            .style(styles::text_good()),
+        chunks[3],
+    );
 }
 
-fn draw_subprojects<B: Backend>(frame: &mut Frame<B>, project: &Project, rect: Rect) {
-    let subproject_count = project.subprojects.len() as u16;
+fn draw_today<B: Backend>(frame: &mut Frame<B>, state: &App, rect: Rect) {
+    let today = Local::now().date_naive();
+    let mut items = Vec::new();
+    let mut item_styles = Vec::new();
+    for location in state.today.iter() {
+        let Some((project, subproject, task)) = state.journal.task_at(*location) else {
+            continue;
+        };
+        let due = task.due_at.as_deref().unwrap_or_default();
+        items.push(format!(
+            "{due}  {}  ({} / {})",
+            task.desc, project.name, subproject.name
+        ));
+        item_styles.push(
+            task.due_date()
+                .filter(|due| *due < today)
+                .map(|_| styles::text_warning()),
+        );
+    }
+    let list = ListWidget::new(items, state.today.selection())
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    format!("Due Today ({})", state.today.len()),
+                    styles::title(),
+                ))
+                .borders(Borders::ALL)
+                .border_style(styles::border_highlighted()),
+        )
+        .bullets(state.settings.bullets())
+        .item_styles(item_styles);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(list, rect);
+}
+
+fn draw_task_info<B: Backend>(frame: &mut Frame<B>, task: &Task, rect: Rect, settings: &Settings) {
+    let now = Local::now();
+    let timestamp = |timestamp: &str| settings.format_timestamp(timestamp, now);
+    let field = |name: &'static str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{name:>10}: "), styles::text_dim()),
+            Span::styled(value, styles::text()),
+        ])
+    };
+    let mut lines = vec![
+        field("Created", timestamp(&task.created_at)),
+        field(
+            "Completed",
+            task.completed_at
+                .as_deref()
+                .map_or_else(|| "-".to_owned(), timestamp),
+        ),
+        field("Due", task.due_at.clone().unwrap_or_else(|| "-".to_owned())),
+        field(
+            "Priority",
+            PRIORITY_LABELS
+                .get(task.priority as usize)
+                .map_or("-", |label| label)
+                .to_owned(),
+        ),
+        field(
+            "Length",
+            format!(
+                "{} words, {} characters",
+                task.word_count(),
+                task.char_count()
+            ),
+        ),
+        Spans::default(),
+    ];
+    lines.extend(markdown::to_spans(
+        &task.desc,
+        styles::list_text(),
+        styles::list_code(),
+    ));
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title(Span::styled("Task Info", styles::title()))
+            .borders(Borders::ALL)
+            .border_style(styles::border_highlighted()),
+    );
+    frame.render_widget(Clear, rect);
+    frame.render_widget(paragraph, rect);
+}
+
+fn draw_project<B: Backend>(
+    frame: &mut Frame<B>,
+    project: &Project,
+    rect: Rect,
+    settings: &Settings,
+) {
+    let inline_prompt = match project.is_locked() {
+        true => {
+            draw_locked_project(frame, rect);
+            false
+        }
+        false => draw_subprojects(frame, project, rect, settings),
+    };
+    if project.prompt_request.is_some() && !inline_prompt {
+        project.prompt.draw(frame, rect, settings);
+    };
+}
+
+fn draw_locked_project<B: Backend>(frame: &mut Frame<B>, rect: Rect) {
+    let paragraph = Paragraph::new(Span::styled(
+        "Project is locked, press Enter to unlock",
+        styles::text_dim(),
+    ))
+    .alignment(tui::layout::Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(styles::border()),
+    );
+    frame.render_widget(paragraph, center_rect(rect.width, 3, rect, 0));
+}
+
+/// Returns true if the project prompt was drawn inline over the selected task
+fn draw_subprojects<B: Backend>(
+    frame: &mut Frame<B>,
+    project: &Project,
+    rect: Rect,
+    settings: &Settings,
+) -> bool {
+    let now = Local::now();
+    let mut inline_prompt = false;
+    for (index, chunk) in subproject_areas(project, rect) {
+        let subproject = project
+            .subprojects
+            .get_item(Some(index))
+            .expect("visible indices should be in range");
+        let mut border_style = styles::border();
+        let mut title_style = styles::title_dim();
+        let mut focus = false;
+        if Some(index) == project.subprojects.selection() {
+            border_style = styles::border_highlighted();
+            title_style = styles::title();
+            focus = true;
+        }
+        let editing = focus
+            && settings.inline_rename
+            && matches!(project.prompt_request, Some(JournalPrompt::RenameTask));
+        let mut title = vec![
+            Span::styled(&subproject.name, title_style),
+            Span::styled(
+                format!(" {}", selection_position(&subproject.tasks)),
+                styles::text_dim(),
+            ),
+        ];
+        if let Some(key) = subproject.sort_key {
+            title.push(Span::styled(
+                format!(" [by {}]", key.label()),
+                styles::text_dim(),
+            ));
+        }
+        if let Some(filter) = &project.filter {
+            let matching = subproject
+                .tasks
+                .iter()
+                .filter(|t| filter.matches(t))
+                .count();
+            title.push(Span::styled(
+                format!(" ({matching} matching)"),
+                styles::text_dim(),
+            ));
+        }
+        let block = Block::default()
+            .title(Spans::from(title))
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        let inner = block.inner(chunk);
+        let widget = ListWidget::new(subproject.tasks.as_strings(), subproject.tasks.selection())
+            .block(block)
+            .bullets(settings.bullets())
+            .child_bullet(settings.bullet_child)
+            .depths(subproject.tasks.iter().map(|task| task.depth).collect())
+            .bullet_colors(
+                subproject
+                    .tasks
+                    .iter()
+                    .map(|task| task.color.as_deref().and_then(styles::label_color))
+                    .collect(),
+            )
+            .item_styles(
+                subproject
+                    .tasks
+                    .iter()
+                    .map(|task| {
+                        let style = match &project.filter {
+                            Some(filter) if !filter.matches(task) => Some(styles::list_text_dim()),
+                            _ => settings
+                                .is_stale(task, now)
+                                .then(|| Style::default().fg(settings.stale_color)),
+                        };
+                        match task.marked {
+                            true => Some(style.unwrap_or_default().patch(styles::list_marked())),
+                            false => style,
+                        }
+                    })
+                    .collect(),
+            )
+            .suffixes(match project.show_ages {
+                true => subproject
+                    .tasks
+                    .iter()
+                    .map(|task| task_age(task, now))
+                    .collect(),
+                false => Vec::new(),
+            })
+            .focus(focus)
+            .editing(editing);
+        frame.render_widget(widget, chunk);
+        if focus && subproject.tasks.is_empty() && inner.area() > 0 {
+            let hint = Paragraph::new(Span::styled("Press n to add a task", styles::text_dim()))
+                .alignment(tui::layout::Alignment::Center);
+            frame.render_widget(hint, center_rect(inner.width, 1, inner, 0));
+        }
+        if let (true, Some(selected)) = (editing, subproject.tasks.selection()) {
+            let depth = subproject.tasks.selected().map_or(0, |task| task.depth);
+            let row = selected_row(inner, selected, depth);
+            if row.area() > 0 {
+                project.prompt.draw_inline(frame, row);
+                inline_prompt = true;
+            }
+        }
+        if settings.show_progress && chunk.width > 2 && chunk.height > 1 {
+            let bar_area = Rect {
+                x: chunk.x + 1,
+                y: chunk.bottom() - 1,
+                width: chunk.width - 2,
+                height: 1,
+            };
+            let bar = progress_bar(
+                subproject.completed_count(),
+                subproject.tasks.len(),
+                bar_area.width as usize,
+            );
+            frame.render_widget(Paragraph::new(Span::styled(bar, border_style)), bar_area);
+        }
+    }
+    inline_prompt
+}
+
+/// Area of each visible subproject, by subproject index
+fn subproject_areas(project: &Project, rect: Rect) -> Vec<(usize, Rect)> {
+    let subproject_index = project.subprojects.selection();
+    // Empty subprojects may be hidden, but the focused subproject is always shown
+    let visible: Vec<usize> = project
+        .subprojects
+        .iter()
+        .enumerate()
+        .filter(|(i, s)| !project.hide_empty || !s.tasks.is_empty() || subproject_index == Some(*i))
+        .map(|(i, _)| i)
+        .collect();
+    let subproject_count = visible.len() as u16;
     let percent_unfocus = if subproject_count > 1 {
         let remainder = 100. - project.focused_width_percent as f32;
-        (remainder / (subproject_count as f32 - 1.).floor()) as u16
+        (remainder / (subproject_count as f32 - 1.)).floor() as u16
     } else {
         100
     };
-    let subproject_index = project.subprojects.selection();
-    let constraints: Vec<Constraint> = (0..subproject_count)
+    let constraints: Vec<Constraint> = visible
+        .iter()
         .map(|i| {
-            if subproject_index == Some(i as usize) {
+            if subproject_index == Some(*i) {
                 Constraint::Percentage(project.focused_width_percent)
             } else {
                 Constraint::Percentage(percent_unfocus)
@@ -205,23 +774,282 @@ fn draw_subprojects<B: Backend>(frame: &mut Frame<B>, project: &Project, rect: R
         .direction(direction)
         .constraints(constraints)
         .split(rect);
-    for (index, subproject) in project.subprojects.iter().enumerate() {
-        let mut border_style = styles::border();
-        let mut title_style = styles::title_dim();
-        let mut focus = false;
-        if Some(index) == project.subprojects.selection() {
-            border_style = styles::border_highlighted();
-            title_style = styles::title();
-            focus = true;
+    visible.into_iter().zip(chunks).collect()
+}
+
+/// Subproject at a screen position, and the task in the row at that position
+pub fn task_at(
+    project: &Project,
+    rect: Rect,
+    column: u16,
+    row: u16,
+) -> Option<(usize, Option<usize>)> {
+    let (index, chunk) = subproject_areas(project, rect)
+        .into_iter()
+        .find(|(_, chunk)| chunk.intersects(Rect::new(column, row, 1, 1)))?;
+    let tasks = &project.subprojects.get_item(Some(index))?.tasks;
+    let inner = Block::default().borders(Borders::ALL).inner(chunk);
+    Some((index, row_at(inner, tasks.selection(), tasks.len(), row)))
+}
+
+/// Bar of `width` cells filled in proportion to completed / total
+fn progress_bar(completed: usize, total: usize, width: usize) -> String {
+    let filled = match total {
+        0 => 0,
+        _ => (width * completed.min(total) + total / 2) / total,
+    };
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Position of the selection within the list, e.g. "4/17" (0 when nothing is selected)
+fn selection_position<T>(list: &SelectionList<T>) -> String {
+    format!("{}/{}", list.selection().map_or(0, |i| i + 1), list.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::data::{ConfirmRequest, Error, FileRequest, SubProject};
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+    use tui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn task_info_shows_priority() {
+        let mut task = Task::new("Review");
+        task.priority = 3;
+        let rows = draw_rows(60, 11, |frame| {
+            draw_task_info(frame, &task, frame.size(), &Settings::default());
+        });
+        assert!(rows.iter().any(|row| row.contains("Priority: High")));
+    }
+
+    #[test]
+    fn hidden_subprojects_give_their_width_to_the_rest() {
+        let mut project = Project::new("Layout");
+        project.subprojects = SelectionList::from(
+            ["Empty", "Focused", "Full", "Also empty"]
+                .map(SubProject::new)
+                .to_vec(),
+        );
+        project
+            .subprojects
+            .get_item_mut(Some(2))
+            .unwrap()
+            .tasks
+            .push_item(Task::new("Task"));
+        project.subprojects.select(1).unwrap();
+        project.focused_width_percent = 60;
+        project.hide_empty = true;
+        let rect = Rect::new(0, 0, 100, 10);
+        let areas = subproject_areas(&project, rect);
+        // The focused subproject is shown although empty
+        let indices: Vec<usize> = areas.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, [1, 2]);
+        assert_eq!(areas[0].1.width, 60);
+        assert_eq!(areas[1].1.width, 40);
+        project.hide_empty = false;
+        let widths: Vec<u16> = subproject_areas(&project, rect)
+            .iter()
+            .map(|(_, area)| area.width)
+            .collect();
+        assert_eq!(widths.iter().sum::<u16>(), 100);
+        assert_eq!(widths[1], 60);
+    }
+
+    #[test]
+    fn formats_selection_position() {
+        let mut list = SelectionList::from(vec!["a"; 17]);
+        assert_eq!(selection_position(&list), "0/17");
+        list.select(3).unwrap();
+        assert_eq!(selection_position(&list), "4/17");
+        assert_eq!(selection_position(&SelectionList::<Task>::default()), "0/0");
+    }
+
+    #[test]
+    fn truncates_path_in_the_middle() {
+        let path = ["Journal", "Long project name", "Leaf"];
+        assert_eq!(
+            truncate_path(&path, 40),
+            "Journal / Long project name / Leaf"
+        );
+        assert_eq!(truncate_path(&path, 20), "Journal / Lo… / Leaf");
+        assert_eq!(truncate_path(&path, 20).width(), 20);
+        // Too narrow for any of the head: the leaf alone
+        assert_eq!(truncate_path(&path, 8), "Leaf");
+    }
+
+    #[test]
+    fn formats_clock_as_hours_and_minutes() {
+        let time = Local.with_ymd_and_hms(2023, 4, 5, 9, 7, 42).unwrap();
+        assert_eq!(clock_text(&time), "09:07");
+    }
+
+    #[test]
+    fn progress_bar_fills_in_proportion() {
+        assert_eq!(progress_bar(1, 4, 8), "██░░░░░░");
+        assert_eq!(progress_bar(1, 3, 10), "███░░░░░░░");
+        assert_eq!(progress_bar(4, 4, 4), "████");
+        assert_eq!(progress_bar(0, 0, 4), "░░░░");
+        assert_eq!(progress_bar(5, 4, 4), "████");
+    }
+
+    #[test]
+    fn resolves_task_under_position() {
+        let mut project = Project::new("Drag");
+        let subproject = |name| {
+            let mut subproject = SubProject::new(name);
+            for index in 0..12 {
+                subproject
+                    .tasks
+                    .push_item(Task::new(&format!("Task {index}")));
+            }
+            subproject
+        };
+        project.subprojects = vec![subproject("Left"), subproject("Right")].into();
+        project.subprojects.select(0).unwrap();
+        project.focused_width_percent = 50;
+        let rect = Rect::new(0, 0, 40, 10);
+        // Rows start inside the border
+        assert_eq!(task_at(&project, rect, 5, 1), Some((0, Some(0))));
+        assert_eq!(task_at(&project, rect, 25, 3), Some((1, Some(2))));
+        assert_eq!(task_at(&project, rect, 25, 0), Some((1, None)));
+        // Scrolled to keep the last task in view
+        let right = project.subprojects.get_item_mut(Some(1)).unwrap();
+        right.tasks.select(11).unwrap();
+        assert_eq!(task_at(&project, rect, 25, 8), Some((1, Some(11))));
+        assert_eq!(task_at(&project, rect, 25, 1), Some((1, Some(4))));
+        assert_eq!(task_at(&project, rect, 45, 1), None);
+    }
+
+    fn draw_rows(width: u16, height: u16, f: impl FnOnce(&mut Frame<TestBackend>)) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(f).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hints_at_adding_tasks_to_empty_focused_subproject() {
+        let mut project = Project::new("Empty");
+        project.subprojects = vec![SubProject::new("Focused"), SubProject::new("Other")].into();
+        project.subprojects.select(0).unwrap();
+        let settings = Settings::default();
+        let rows = draw_rows(80, 10, |frame| {
+            draw_subprojects(frame, &project, frame.size(), &settings);
+        });
+        let hints: Vec<usize> = rows
+            .iter()
+            .filter_map(|row| row.find("Press n to add a task"))
+            .collect();
+        // Only in the focused (left) pane
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0] < 40);
+    }
+
+    #[test]
+    fn hints_follow_the_state() {
+        let mut state = App::new(PathBuf::new());
+        assert!(hint_text(&state).starts_with("n:new"));
+        if let Some(project) = state.journal.project() {
+            project.prompt_request = Some(JournalPrompt::AddTasksBulk);
         }
-        let widget = ListWidget::new(subproject.tasks.as_strings(), subproject.tasks.selection())
-            .block(
-                Block::default()
-                    .title(Span::styled(&subproject.name, title_style))
-                    .borders(Borders::ALL)
-                    .border_style(border_style),
-            )
-            .focus(focus);
-        frame.render_widget(widget, chunks[index]);
+        assert_eq!(hint_text(&state), "Ctrl+s:add  Esc:cancel");
+        state.file_request = Some(FileRequest::Load);
+        assert!(hint_text(&state).contains("t:view trash"));
+        state.confirm_request = Some(ConfirmRequest::EmptyTrash);
+        assert_eq!(hint_text(&state), state.settings.confirm_hint());
+        state.locked = true;
+        assert_eq!(hint_text(&state), "Enter:unlock  Ctrl+q:quit");
+    }
+
+    #[test]
+    fn disabled_status_segments_are_not_drawn() {
+        let mut state = App::new(PathBuf::new());
+        state.read_only = true;
+        let size = Rect::new(0, 0, 80, 24);
+        let status = |state: &App| -> String {
+            let spans = status_spans(&state.settings.status_right, state, size, 40);
+            spans.0.into_iter().map(|span| span.content).collect()
+        };
+        let text = status(&state);
+        assert!(text.contains("READ ONLY"));
+        assert!(text.contains("80×24"));
+        state
+            .settings
+            .status_right
+            .retain(|segment| *segment != StatusSegment::ReadOnly);
+        let text = status(&state);
+        assert!(!text.contains("READ ONLY"));
+        assert!(text.contains("80×24"));
+    }
+
+    #[test]
+    fn project_color_tints_its_tab() {
+        let mut project = Project::new("Tinted");
+        assert_eq!(tab_color(&project), Style::default());
+        project.color = Some("cyan".to_owned());
+        assert_eq!(tab_color(&project).fg, Some(Color::Cyan));
+        // The selected tab keeps its emphasis
+        let highlight = styles::tab().patch(tab_color(&project));
+        assert_eq!(highlight.fg, Some(Color::Cyan));
+        assert!(highlight.add_modifier.contains(tui::style::Modifier::BOLD));
+        project.color = Some("no such color".to_owned());
+        assert_eq!(tab_color(&project), Style::default());
+    }
+
+    #[test]
+    fn tab_bar_scrolls_to_the_selected_tab() {
+        assert_eq!(tab_offset(&[10, 10, 10], 0, 15), 0);
+        assert_eq!(tab_offset(&[10, 10, 10], 2, 15), 2);
+        assert_eq!(tab_offset(&[10, 10, 10], 2, 25), 1);
+        let mut state = App::new(PathBuf::new());
+        state.journal.projects = SelectionList::from(
+            (0..8)
+                .map(|i| Project::new(&format!("Project {i}")))
+                .collect::<Vec<_>>(),
+        );
+        state.journal.projects.select(7).unwrap();
+        let rows = draw_rows(80, 2, |frame| draw_tab_bar(frame, &state, frame.size()));
+        assert!(rows[0].contains("Project 7"));
+        assert!(!rows[0].contains("Project 0"));
+        assert!(rows[0][rows[0].find('│').unwrap()..].contains('…'));
+    }
+
+    #[test]
+    fn feedback_uses_theme_style() {
+        let mut state = App::new(PathBuf::new());
+        state.settings.feedback_styles.error = Style::default().fg(Color::Blue);
+        state.add_feedback(Error::from("Broken"));
+        let mut terminal = Terminal::new(TestBackend::new(40, 1)).unwrap();
+        terminal
+            .draw(|frame| draw_status_bar(frame, &state, frame.size()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let x = (0..40).find(|x| buffer.get(*x, 0).symbol == "B").unwrap();
+        assert_eq!(buffer.get(x, 0).fg, Color::Blue);
+    }
+
+    #[test]
+    fn formats_task_age_in_largest_unit() {
+        let now = Local.with_ymd_and_hms(2023, 4, 5, 12, 0, 0).unwrap();
+        let age = |created_at: &str| {
+            let task = Task {
+                created_at: created_at.to_owned(),
+                ..Task::new("Aging")
+            };
+            task_age(&task, now)
+        };
+        assert_eq!(age("2023-04-05 12:00:00").as_deref(), Some("now"));
+        assert_eq!(age("2023-04-05 11:15:00").as_deref(), Some("45m"));
+        assert_eq!(age("2023-04-04 13:00:00").as_deref(), Some("23h"));
+        assert_eq!(age("2023-04-03 12:00:00").as_deref(), Some("2d"));
+        assert_eq!(age("not a timestamp"), None);
     }
 }
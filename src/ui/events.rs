@@ -1,12 +1,57 @@
-use super::widgets::{files::FileListResult, prompt::PromptEvent};
+use super::widgets::{
+    choice::ChoiceEvent,
+    confirm::ConfirmEvent,
+    files::{FileListResult, CHECKPOINTS_DIR, TEMPLATES_DIR},
+    prompt::PromptEvent,
+};
 use crate::app::data::{
-    filename, App, AppPrompt, DataDeserialize, DataSerialize, Error, FileRequest, Journal,
-    JournalPrompt, Project, Result, SubProject, Task, DEFAULT_WIDTH_PERCENT,
+    filename, is_journal_file, normalize_name, App, AppPrompt, ChoiceRequest, ConfirmRequest,
+    DataDeserialize, DataSerialize, Error, Feedback, FileRequest, FocusRegion, Journal,
+    JournalPrompt, LoadError, Overlay, Project, Result, SubProject, Task, TaskFilter, TaskSortKey,
+    CLEAR_CONFIRM_THRESHOLD, COUNT_PREFIX_TIMEOUT_MS, DATE_FORMAT, DEFAULT_WIDTH_PERCENT,
+    PRIORITY_LABELS, TASK_COLORS,
+};
+use crate::app::export::{
+    load_json, project_markdown, subproject_markdown, tasks_markdown, to_markdown, ExportFormat,
+    EXPORTS_DIR, MIRROR_SUFFIX,
 };
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use std::{path::PathBuf, process::Command};
+use crate::app::list::SelectionList;
+use crate::app::settings::{platform_opener, Settings};
+use crate::crypto::Password;
+use chrono::{Local, NaiveDate};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant, SystemTime},
+};
+use tui::layout::Rect;
 
 pub fn handle_event(key: KeyEvent, state: &mut App) {
+    state.last_input = Instant::now();
+    // A count prefix applies to the next key only
+    let count = state.count_prefix.take();
+    if (key.code, key.modifiers) == (KeyCode::Char('q'), KeyModifiers::CONTROL) {
+        return request_quit(state);
+    }
+    if state.locked {
+        return handle_app_prompt_event(key, state);
+    }
+    if state.debug {
+        // Other keys would act on the hidden view
+        if matches!(key.code, KeyCode::F(12) | KeyCode::Esc) {
+            state.debug = false;
+        }
+        return;
+    }
+    let count = match count {
+        Some(number) if !takes_count(key) && !is_digit(key) && !is_modal(state) => {
+            select_project_number(state, number);
+            None
+        }
+        count => count,
+    };
     if !handle_global_event(key, state) {
         let is_prompt = state
             .journal
@@ -14,14 +59,218 @@ pub fn handle_event(key: KeyEvent, state: &mut App) {
             .map_or_else(|| false, |p| p.prompt_request.is_some());
         if state.prompt_request.is_some() {
             handle_app_prompt_event(key, state);
+        } else if let Some(request) = state.confirm_request {
+            handle_confirm_event(key, state, request);
+        } else if let Some(request) = state.choice_request {
+            handle_choice_event(key, state, request);
+        } else if let Some(overlay) = state.overlay {
+            handle_overlay_event(key, state, overlay);
         } else if state.file_request.is_some() {
             handle_filelist_event(key, state);
         } else if is_prompt {
             handle_journal_prompt_event(key, state);
         } else {
-            handle_journal_event(key, state);
+            handle_journal_event(key, state, count);
+        }
+    };
+}
+
+fn request_quit(state: &mut App) {
+    if !state.is_dirty() {
+        state.quit = true;
+        return;
+    }
+    // The locked view cannot show a dialog
+    if state.locked {
+        return quit_locked(state);
+    }
+    if !state.settings.confirm_quit {
+        state.quit = true;
+        return;
+    }
+    state.confirm.set_message("Unsaved changes, quit anyway?");
+    state.confirm_request = Some(ConfirmRequest::Quit);
+}
+
+/// Save unsaved changes and quit, or stay locked if they cannot be saved without the user
+fn quit_locked(state: &mut App) {
+    if state.read_only {
+        state.quit = true;
+        return;
+    }
+    if file_modified(&state.filepath) != state.file_modified {
+        return state.add_feedback(Error::from("Journal changed on disk, unlock to quit"));
+    }
+    match save_state(state, None) {
+        Err(e) => state.add_feedback(Error::from_cause("Failed to save, unlock to quit", e)),
+        Ok(_) => state.quit = true,
+    }
+}
+
+pub fn handle_tick(state: &mut App) {
+    state.filelist.poll();
+    let count_timeout = Duration::from_millis(COUNT_PREFIX_TIMEOUT_MS);
+    if state.last_input.elapsed() >= count_timeout && !is_modal(state) {
+        if let Some(number) = state.count_prefix.take() {
+            select_project_number(state, number);
+        }
+    }
+    if state.is_autosave_due() {
+        autosave(state);
+    }
+    if !state.locked && !state.journal.password.is_empty() && state.is_inactive() {
+        lock(state);
+    }
+}
+
+/// Save changes if possible without interrupting the user. An unencrypted journal is only saved
+/// if allowed by the settings, otherwise the user is asked once to set a password.
+fn autosave(state: &mut App) {
+    state.autosave_input = Some(state.last_input);
+    if state.locked || state.read_only || !state.filepath.exists() || !state.is_dirty() {
+        return;
+    }
+    if state.journal.password.is_empty() && !state.settings.autosave_unencrypted {
+        if !state.autosave_prompted && !is_modal(state) {
+            state.autosave_prompted = true;
+            if let Some(project) = state.journal.project() {
+                set_project_prompt(
+                    project,
+                    JournalPrompt::SetPassword,
+                    "Set a password to enable autosave:",
+                    "",
+                    true,
+                );
+            }
+        }
+        return;
+    }
+    if file_modified(&state.filepath) != state.file_modified {
+        return state.add_feedback(Feedback::warning("Journal changed on disk, not autosaved"));
+    }
+    if let Err(e) = save_state(state, None) {
+        state.add_feedback(Feedback::critical(Error::from_cause(
+            "Failed to autosave",
+            e,
+        )));
+    }
+}
+
+/// Whether a prompt, dialog or overlay is open
+fn is_modal(state: &App) -> bool {
+    state.prompt_request.is_some()
+        || state.confirm_request.is_some()
+        || state.choice_request.is_some()
+        || state.file_request.is_some()
+        || state.overlay.is_some()
+        || state
+            .journal
+            .projects
+            .selected()
+            .is_some_and(|p| p.prompt_request.is_some())
+}
+
+fn lock(state: &mut App) {
+    state.locked = true;
+    let prompt_text = format!("`{}` is locked, password:", state.journal.name);
+    set_app_prompt(state, AppPrompt::Unlock, &prompt_text, "", true);
+}
+
+pub fn handle_paste(text: &str, state: &mut App) {
+    state.last_input = Instant::now();
+    if state.prompt_request.is_some() {
+        state.prompt.paste(text);
+    } else if state.file_request.is_some() {
+        state.filelist.paste(text);
+    } else if let Some(project) = state.journal.project() {
+        if project.prompt_request.is_some() {
+            project.prompt.paste(text);
+        }
+    }
+}
+
+pub fn handle_mouse(mouse: MouseEvent, state: &mut App, size: Rect) {
+    state.last_input = Instant::now();
+    let drag = match mouse.kind {
+        MouseEventKind::Up(MouseButton::Left) => state.drag.take(),
+        _ => None,
+    };
+    let drag = drag.filter(|_| !state.read_only);
+    if state.locked
+        || state.prompt_request.is_some()
+        || state.choice_request.is_some()
+        || state.confirm_request.is_some()
+        || state.overlay.is_some()
+        || state.file_request.is_some()
+    {
+        return;
+    }
+    let area = super::layout(size, &state.settings)[1];
+    let Some(project) = state.journal.project() else {
+        return;
+    };
+    if project.is_locked() || project.prompt_request.is_some() {
+        return;
+    }
+    let Some((subproject_index, task_index)) =
+        super::task_at(project, area, mouse.column, mouse.row)
+    else {
+        return;
+    };
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            project.subprojects.select(subproject_index).ok();
+            bind_focus_size(project, &state.settings);
+            if let (Some(subproject), Some(task_index)) = (project.subproject(), task_index) {
+                subproject.tasks.select(task_index).ok();
+                state.drag = Some((subproject_index, task_index));
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            if let Some(source) = drag {
+                drop_task(
+                    project,
+                    source,
+                    (subproject_index, task_index),
+                    &state.settings,
+                );
+            }
         }
+        _ => (),
+    }
+}
+
+/// Move a dragged task to the target row, or to the end of the target subproject
+fn drop_task(
+    project: &mut Project,
+    source: (usize, usize),
+    target: (usize, Option<usize>),
+    settings: &Settings,
+) {
+    let Some(subproject) = project.subprojects.get_item_mut(Some(source.0)) else {
+        return;
+    };
+    if subproject.tasks.select(source.1).is_err() {
+        return;
+    }
+    if source.0 == target.0 {
+        let last = subproject.tasks.len().saturating_sub(1);
+        subproject.tasks.move_to(target.1.unwrap_or(last)).ok();
+        subproject.sort_key = None;
+        subproject.sort_tasks();
+        return;
+    }
+    let Some(task) = subproject.tasks.pop_selected() else {
+        return;
     };
+    let Some(target_subproject) = project.subprojects.get_item_mut(Some(target.0)) else {
+        return;
+    };
+    let index = target.1.unwrap_or(target_subproject.tasks.len());
+    target_subproject.tasks.insert_item(Some(index), task, true);
+    target_subproject.sort_tasks();
+    project.subprojects.select(target.0).ok();
+    bind_focus_size(project, settings);
 }
 
 fn handle_global_event(key: KeyEvent, state: &mut App) -> bool {
@@ -29,19 +278,79 @@ fn handle_global_event(key: KeyEvent, state: &mut App) -> bool {
         // Global operations
         (KeyCode::Char('o'), KeyModifiers::ALT) => {
             if let Err(e) = open_datadir(state) {
-                state.add_feedback(Error::from_cause("Failed to save file", e));
+                state.add_feedback(Error::from_cause("Failed to open data directory", e));
             };
         }
+        (KeyCode::Char('n'), KeyModifiers::CONTROL) | (KeyCode::F(2), KeyModifiers::NONE)
+            if state.read_only =>
+        {
+            state.add_feedback(Error::from("Journal is read-only"));
+        }
         (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
             set_app_prompt(state, AppPrompt::NewJournal, "New file name:", "", false);
         }
+        (KeyCode::F(2), KeyModifiers::NONE) => {
+            let prefill = filename(&state.filepath);
+            set_app_prompt(
+                state,
+                AppPrompt::RenameFile,
+                "Rename file:",
+                &prefill,
+                false,
+            );
+        }
+        (KeyCode::F(3), KeyModifiers::NONE) => {
+            state.settings.show_hints = !state.settings.show_hints;
+        }
+        (KeyCode::F(12), KeyModifiers::NONE) => state.debug = true,
+        (KeyCode::F(7), KeyModifiers::NONE) => {
+            state.settings.password_dots = !state.settings.password_dots;
+        }
+        (KeyCode::F(8), KeyModifiers::NONE) => {
+            state.read_only = !state.read_only;
+            state.add_feedback(match state.read_only {
+                true => "Read-only mode",
+                false => "Editing enabled",
+            });
+        }
         _ => return false,
     };
     true
 }
 
-fn handle_journal_event(key: KeyEvent, state: &mut App) {
+#[derive(Clone, Copy)]
+enum Motion {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+fn handle_journal_event(key: KeyEvent, state: &mut App, count: Option<usize>) {
+    let locked = state
+        .journal
+        .projects
+        .selected()
+        .is_some_and(|p| p.is_locked());
+    if locked && !is_journal_key(key) {
+        return handle_locked_project_event(key, state);
+    }
+    if state.read_only && is_mutating_key(key, state) {
+        return state.add_feedback(Error::from("Journal is read-only"));
+    }
+    if let Some(missing) = missing_target(key, state) {
+        return state.add_feedback(missing);
+    }
     match (key.code, key.modifiers) {
+        // Filter
+        _ if is_filter_jump(key, state) => {
+            if let Some(project) = state.journal.project() {
+                match project.select_match(key.code == KeyCode::Char('N')) {
+                    true => bind_focus_size(project, &state.settings),
+                    false => state.add_feedback(Error::from("No matching tasks")),
+                }
+            }
+        }
         // New
         (KeyCode::Char('n'), KeyModifiers::ALT) => {
             if let Some(project) = state.journal.project() {
@@ -70,6 +379,17 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
                 set_project_prompt(project, JournalPrompt::AddTask, "New Task:", "", false);
             }
         }
+        (KeyCode::Char('A'), KeyModifiers::SHIFT) => {
+            if let Some(project) = state.journal.project() {
+                set_project_prompt(
+                    project,
+                    JournalPrompt::AddTasksBulk,
+                    "New Tasks, one per line (Ctrl+s to add):",
+                    "",
+                    false,
+                );
+            }
+        }
         // Rename
         (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
             let prefill = state.journal.name.clone();
@@ -131,14 +451,200 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
                 }
             }
         }
+        // Task
+        (KeyCode::Char(' '), KeyModifiers::NONE) => {
+            if let Some(project) = state.journal.project() {
+                if let Some(subproject) = project.subproject() {
+                    subproject.toggle_task_completed();
+                    subproject.sort_tasks();
+                }
+            }
+        }
+        (KeyCode::Char('X' | 'U'), KeyModifiers::SHIFT) => {
+            let completed = key.code == KeyCode::Char('X');
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                let count = subproject.set_all_completed(completed);
+                subproject.sort_tasks();
+                state.add_feedback(match completed {
+                    true => format!("Completed {count} tasks"),
+                    false => format!("Reopened {count} tasks"),
+                });
+            }
+        }
+        (KeyCode::Char('c'), KeyModifiers::NONE) => {
+            if let Some(task) = state.journal.selected_task() {
+                let selected = task
+                    .color
+                    .as_ref()
+                    .and_then(|color| TASK_COLORS.iter().position(|c| c == color))
+                    .map_or(0, |i| i + 1);
+                let labels: Vec<&str> = ["none"].into_iter().chain(TASK_COLORS).collect();
+                state.choice.set_options("Color", &labels, selected);
+                state.choice_request = Some(ChoiceRequest::Color);
+            }
+        }
+        (KeyCode::Char('P'), KeyModifiers::SHIFT) => {
+            if let Some(task) = state.journal.selected_task() {
+                let selected = task.priority as usize;
+                state
+                    .choice
+                    .set_options("Priority", &PRIORITY_LABELS, selected);
+                state.choice_request = Some(ChoiceRequest::Priority);
+            }
+        }
+        (KeyCode::Char('o'), KeyModifiers::NONE) => {
+            if let Some(task) = state.journal.selected_task() {
+                let urls = task.urls();
+                match urls.len() {
+                    0 => state.add_feedback(Error::from("No link in task")),
+                    1 => {
+                        let url = urls[0].to_owned();
+                        open_url(state, &url);
+                    }
+                    _ => {
+                        state.choice.set_options("Open link", &urls, 0);
+                        state.choice_request = Some(ChoiceRequest::Url);
+                    }
+                }
+            }
+        }
+        (KeyCode::Char('Y'), KeyModifiers::SHIFT) => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                let (name, text) = (subproject.name.clone(), subproject_markdown(subproject, 2));
+                match copy_to_clipboard(state, text) {
+                    Ok(_) => state.add_feedback(format!("Copied `{name}` to clipboard")),
+                    Err(e) => state.add_feedback(Error::from_cause("Failed to copy", e)),
+                }
+            }
+        }
+        (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                let count = subproject.tasks.iter().filter(|t| t.marked).count();
+                if count == 0 {
+                    return state.add_feedback(Error::from("No tasks marked"));
+                }
+                let text = tasks_markdown(subproject.tasks.iter().filter(|t| t.marked));
+                match copy_to_clipboard(state, text) {
+                    Ok(_) => state.add_feedback(format!("Copied {count} tasks to clipboard")),
+                    Err(e) => state.add_feedback(Error::from_cause("Failed to copy", e)),
+                }
+            }
+        }
+        (KeyCode::Char('y'), KeyModifiers::ALT) => {
+            if let Some(project) = state.journal.project() {
+                let (name, text) = (project.name.clone(), project_markdown(project, 1));
+                match copy_to_clipboard(state, text) {
+                    Ok(_) => state.add_feedback(format!("Copied `{name}` to clipboard")),
+                    Err(e) => state.add_feedback(Error::from_cause("Failed to copy", e)),
+                }
+            }
+        }
+        (KeyCode::Char('S'), KeyModifiers::SHIFT) => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                let selected = subproject
+                    .sort_key
+                    .and_then(|key| TaskSortKey::ALL.iter().position(|k| *k == key))
+                    .map_or(0, |i| i + 1);
+                let labels: Vec<&str> = ["manual"]
+                    .into_iter()
+                    .chain(TaskSortKey::ALL.iter().map(|k| k.label()))
+                    .collect();
+                state.choice.set_options("Sort tasks by", &labels, selected);
+                state.choice_request = Some(ChoiceRequest::SortKey);
+            }
+        }
+        (KeyCode::Char('u'), KeyModifiers::NONE) => {
+            if let Some(project) = state.journal.project() {
+                let due_at = project
+                    .subproject()
+                    .and_then(|s| s.task())
+                    .map(|t| t.due_at.clone().unwrap_or_default());
+                if let Some(prefill) = due_at {
+                    set_project_prompt(
+                        project,
+                        JournalPrompt::SetDueDate,
+                        "Due date (YYYY-MM-DD, empty to clear):",
+                        &prefill,
+                        false,
+                    );
+                }
+            }
+        }
+        (KeyCode::Char('m'), KeyModifiers::NONE) => {
+            if let Some(task) = state
+                .journal
+                .project()
+                .and_then(|p| p.subproject())
+                .and_then(|s| s.task())
+            {
+                task.marked = !task.marked;
+            }
+        }
+        (KeyCode::Char('M'), KeyModifiers::SHIFT) => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                let toggled = subproject.toggle_marked_completed();
+                subproject.sort_tasks();
+                match toggled {
+                    (_, 0) => state.add_feedback(Error::from("No tasks marked")),
+                    (true, count) => state.add_feedback(format!("Completed {count} tasks")),
+                    (false, count) => state.add_feedback(format!("Reopened {count} tasks")),
+                }
+            }
+        }
+        (KeyCode::Char('p'), KeyModifiers::NONE) => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                subproject.toggle_task_pinned();
+            }
+        }
+        (KeyCode::Char('i'), KeyModifiers::NONE) if state.journal.selected_task().is_some() => {
+            state.overlay = Some(Overlay::TaskInfo);
+        }
+        (KeyCode::F(4), KeyModifiers::NONE) => state.overlay = Some(Overlay::Stats),
+        (KeyCode::F(5), KeyModifiers::NONE) => state.overlay = Some(Overlay::Activity),
+        (KeyCode::F(6), KeyModifiers::NONE) => {
+            let today = Local::now().date_naive();
+            state.today = SelectionList::from(state.journal.due_tasks(today));
+            match state.today.is_empty() {
+                true => state.add_feedback("No tasks due today"),
+                false => {
+                    state.today.select(0).ok();
+                    state.overlay = Some(Overlay::Today);
+                }
+            }
+        }
         // Delete
         (KeyCode::Char('d'), KeyModifiers::ALT) => {
-            state.journal.projects.pop_selected();
+            if let Some(project) = state.journal.projects.selected() {
+                let message = format!("Delete project `{}`?", project.name);
+                state.confirm.set_message(&message);
+                state.confirm_request = Some(ConfirmRequest::DeleteProject);
+            }
         }
         (KeyCode::Char('D'), KeyModifiers::SHIFT) => {
-            if let Some(project) = state.journal.project() {
-                project.subprojects.pop_selected();
-            };
+            if let Some(subproject) = state
+                .journal
+                .projects
+                .selected()
+                .and_then(|p| p.subprojects.selected())
+            {
+                let message = format!("Delete subproject `{}`?", subproject.name);
+                state.confirm.set_message(&message);
+                state.confirm_request = Some(ConfirmRequest::DeleteSubProject);
+            }
+        }
+        (KeyCode::Char('x'), KeyModifiers::CONTROL) => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                let count = subproject.completed_count();
+                match count > CLEAR_CONFIRM_THRESHOLD {
+                    true => {
+                        let message =
+                            format!("Clear {count} completed tasks from `{}`?", subproject.name);
+                        state.confirm.set_message(&message);
+                        state.confirm_request = Some(ConfirmRequest::ClearCompleted);
+                    }
+                    false => clear_completed(state),
+                }
+            }
         }
         (KeyCode::Char('d'), KeyModifiers::NONE) => {
             if let Some(project) = state.journal.project() {
@@ -148,52 +654,121 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
             }
         }
         // Navigation
+        (KeyCode::Char('/'), KeyModifiers::NONE) => {
+            if let Some(project) = state.journal.project() {
+                let prefill = project
+                    .filter
+                    .as_ref()
+                    .map(|f| f.pattern())
+                    .unwrap_or_default();
+                set_project_prompt(
+                    project,
+                    JournalPrompt::Filter,
+                    "Filter tasks (re: for regex, empty to clear):",
+                    &prefill,
+                    false,
+                );
+            }
+        }
         (KeyCode::Esc, KeyModifiers::NONE) => {
+            // Step up: filter, task, then subproject
             if let Some(project) = state.journal.project() {
-                if let Some(subproject) = project.subproject() {
-                    subproject.tasks.deselect();
+                if project.filter.take().is_some() {
+                    return;
+                }
+                match project.subproject() {
+                    Some(subproject) if subproject.tasks.selection().is_some() => {
+                        subproject.tasks.deselect();
+                        state.focus = FocusRegion::Subprojects;
+                    }
+                    _ => project.subprojects.deselect(),
                 }
             }
         }
-        (KeyCode::Tab, KeyModifiers::NONE) => state.journal.projects.select_next(),
-        (KeyCode::BackTab, _) => state.journal.projects.select_prev(),
-        (KeyCode::PageDown, KeyModifiers::CONTROL) => {
-            state.journal.projects.select_next();
+        (KeyCode::Char('f'), KeyModifiers::NONE) => cycle_focus(state),
+        (KeyCode::Tab, KeyModifiers::NONE) if state.journal.selected_task().is_some() => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                subproject.indent_task();
+            }
+        }
+        (KeyCode::BackTab, _) if state.journal.selected_task().is_some() => {
+            if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+                subproject.outdent_task();
+            }
+        }
+        (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+            set_app_prompt(state, AppPrompt::GoToProject, "Go to project:", "", false);
+        }
+        (KeyCode::Tab, KeyModifiers::NONE) | (KeyCode::PageDown, KeyModifiers::CONTROL) => {
+            let wrap = state.settings.wrap_navigation;
+            state.journal.projects.step_next(wrap);
+        }
+        (KeyCode::BackTab, _) | (KeyCode::PageUp, KeyModifiers::CONTROL) => {
+            let wrap = state.settings.wrap_navigation;
+            state.journal.projects.step_prev(wrap);
+        }
+        (KeyCode::Right | KeyCode::Char('l'), KeyModifiers::NONE) => {
+            navigate(state, Motion::Right, count)
         }
-        (KeyCode::PageUp, KeyModifiers::CONTROL) => {
-            state.journal.projects.select_prev();
+        (KeyCode::Left | KeyCode::Char('h'), KeyModifiers::NONE) => {
+            navigate(state, Motion::Left, count)
         }
-        (KeyCode::Right, KeyModifiers::NONE) => {
+        (KeyCode::Down | KeyCode::Char('j'), KeyModifiers::NONE) => {
+            navigate(state, Motion::Down, count)
+        }
+        (KeyCode::Up | KeyCode::Char('k'), KeyModifiers::NONE) => {
+            navigate(state, Motion::Up, count)
+        }
+        (KeyCode::Home, KeyModifiers::NONE) => {
             if let Some(project) = state.journal.project() {
-                project.subprojects.select_next();
+                project.subprojects.select(0).ok();
+                bind_focus_size(project, &state.settings);
             }
         }
-        (KeyCode::Left, KeyModifiers::NONE) => {
+        (KeyCode::End, KeyModifiers::NONE) => {
             if let Some(project) = state.journal.project() {
-                project.subprojects.select_prev();
+                let last = project.subprojects.len().saturating_sub(1);
+                project.subprojects.select(last).ok();
+                bind_focus_size(project, &state.settings);
             }
         }
-        (KeyCode::Down, KeyModifiers::NONE) => {
+        (KeyCode::Char('g'), KeyModifiers::NONE) => {
             if let Some(project) = state.journal.project() {
                 if let Some(subproject) = project.subproject() {
-                    subproject.tasks.select_next();
+                    subproject.tasks.select(0).ok();
                 }
             }
         }
-        (KeyCode::Up, KeyModifiers::NONE) => {
+        (KeyCode::Char('G'), KeyModifiers::SHIFT) => {
             if let Some(project) = state.journal.project() {
                 if let Some(subproject) = project.subproject() {
-                    subproject.tasks.select_prev();
+                    let last = subproject.tasks.len().saturating_sub(1);
+                    subproject.tasks.select(last).ok();
                 }
             }
         }
         // Shift
+        (KeyCode::PageDown | KeyCode::PageUp | KeyCode::Home | KeyCode::End, KeyModifiers::ALT)
+            if state.journal.alphabetical_projects =>
+        {
+            state.add_feedback(Error::from("Projects are sorted by name"));
+        }
         (KeyCode::PageDown, KeyModifiers::ALT) => {
             state.journal.projects.shift_next().ok();
         }
         (KeyCode::PageUp, KeyModifiers::ALT) => {
             state.journal.projects.shift_prev().ok();
         }
+        (KeyCode::Home, KeyModifiers::ALT) => {
+            // With a count prefix, move to that (1-based) position instead
+            let position = count.map_or(0, |count| count.saturating_sub(1));
+            let last = state.journal.projects.len().saturating_sub(1);
+            state.journal.projects.move_to(position.min(last)).ok();
+        }
+        (KeyCode::End, KeyModifiers::ALT) => {
+            let last = state.journal.projects.len().saturating_sub(1);
+            state.journal.projects.move_to(last).ok();
+        }
         (KeyCode::Right, KeyModifiers::SHIFT) => {
             if let Some(project) = state.journal.project() {
                 project.subprojects.shift_next().ok();
@@ -208,6 +783,8 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
             if let Some(project) = state.journal.project() {
                 if let Some(subproject) = project.subproject() {
                     subproject.tasks.shift_next().ok();
+                    subproject.sort_key = None;
+                    subproject.sort_tasks();
                 }
             }
         }
@@ -215,23 +792,52 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
             if let Some(project) = state.journal.project() {
                 if let Some(subproject) = project.subproject() {
                     subproject.tasks.shift_prev().ok();
+                    subproject.sort_key = None;
+                    subproject.sort_tasks();
+                }
+            }
+        }
+        (KeyCode::Home, KeyModifiers::CONTROL) => {
+            if let Some(project) = state.journal.project() {
+                if let Some(subproject) = project.subproject() {
+                    subproject.tasks.move_to(0).ok();
+                    subproject.sort_key = None;
+                    subproject.sort_tasks();
+                }
+            }
+        }
+        (KeyCode::End, KeyModifiers::CONTROL) => {
+            if let Some(project) = state.journal.project() {
+                if let Some(subproject) = project.subproject() {
+                    let last = subproject.tasks.len().saturating_sub(1);
+                    subproject.tasks.move_to(last).ok();
+                    subproject.sort_key = None;
+                    subproject.sort_tasks();
                 }
             }
         }
         // Move
         (KeyCode::Right, KeyModifiers::CONTROL) => move_task(state, false),
         (KeyCode::Left, KeyModifiers::CONTROL) => move_task(state, true),
+        (KeyCode::Right, KeyModifiers::ALT) => move_marked_tasks(state, false, count),
+        (KeyCode::Left, KeyModifiers::ALT) => move_marked_tasks(state, true, count),
+        (KeyCode::PageDown, KeyModifiers::SHIFT) => move_subproject(state, false),
+        (KeyCode::PageUp, KeyModifiers::SHIFT) => move_subproject(state, true),
         // UI
         (KeyCode::Char('='), KeyModifiers::NONE) => {
             if let Some(project) = state.journal.project() {
-                project.focused_width_percent += 5;
-                bind_focus_size(project);
+                project.focused_width_percent = project
+                    .focused_width_percent
+                    .saturating_add(state.settings.focus_width_step);
+                bind_focus_size(project, &state.settings);
             }
         }
         (KeyCode::Char('-'), KeyModifiers::NONE) => {
             if let Some(project) = state.journal.project() {
-                project.focused_width_percent = project.focused_width_percent.saturating_sub(5);
-                bind_focus_size(project);
+                project.focused_width_percent = project
+                    .focused_width_percent
+                    .saturating_sub(state.settings.focus_width_step);
+                bind_focus_size(project, &state.settings);
             }
         }
         (KeyCode::Char('\\'), KeyModifiers::NONE) => {
@@ -239,6 +845,42 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
                 project.split_vertical = !project.split_vertical;
             }
         }
+        (KeyCode::Char('v'), KeyModifiers::NONE) => {
+            if let Some(project) = state.journal.project() {
+                match project.cycle_layout() {
+                    Some(name) => {
+                        bind_focus_size(project, &state.settings);
+                        state.add_feedback(format!("Layout: {name}"));
+                    }
+                    None => state.add_feedback("No saved layouts, save one with Alt+v"),
+                }
+            }
+        }
+        (KeyCode::Char('v'), KeyModifiers::ALT) => {
+            if let Some(project) = state.journal.project() {
+                set_project_prompt(
+                    project,
+                    JournalPrompt::SaveLayout,
+                    "Layout Name:",
+                    "",
+                    false,
+                );
+            }
+        }
+        (KeyCode::Char('b'), KeyModifiers::CONTROL) => state.settings.toggle_ascii_bullets(),
+        (KeyCode::Char('%'), _) => {
+            state.settings.show_progress = !state.settings.show_progress;
+        }
+        (KeyCode::Char('a'), KeyModifiers::NONE) => {
+            if let Some(project) = state.journal.project() {
+                project.show_ages = !project.show_ages;
+            }
+        }
+        (KeyCode::Char('z'), KeyModifiers::NONE) => {
+            if let Some(project) = state.journal.project() {
+                project.hide_empty = !project.hide_empty;
+            }
+        }
         // File
         (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
             let name = state.journal.name.clone();
@@ -252,6 +894,18 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
                 );
             }
         }
+        (KeyCode::Char('p'), KeyModifiers::ALT) => {
+            if let Some(project) = state.journal.project() {
+                let prompt_text = format!("Set new password for project `{}`:", project.name);
+                set_project_prompt(
+                    project,
+                    JournalPrompt::SetProjectPassword,
+                    &prompt_text,
+                    "",
+                    true,
+                );
+            }
+        }
         (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
             state.file_request = Some(FileRequest::Load);
             state.filelist.reset();
@@ -270,29 +924,350 @@ fn handle_journal_event(key: KeyEvent, state: &mut App) {
             state.filelist.set_title_text("Save Journal:");
             state.filelist.set_prompt_text("Save File As:");
         }
+        (KeyCode::Char('e'), KeyModifiers::ALT) if state.journal.projects.selected().is_some() => {
+            state.file_request = Some(FileRequest::ExportProject);
+            state.filelist.reset();
+            state.filelist.set_title_text("Export Project:");
+            state.filelist.set_prompt_text("Export Project As:");
+        }
+        (KeyCode::Char('i'), KeyModifiers::ALT) => {
+            state.file_request = Some(FileRequest::ImportProject);
+            state.filelist.reset();
+            state.filelist.set_title_text("Import Project:");
+            state.filelist.set_prompt_text("");
+        }
+        (KeyCode::Char('c' | 'C'), modifiers) if modifiers.contains(KeyModifiers::ALT) => {
+            let remove_completed = modifiers.contains(KeyModifiers::SHIFT);
+            state.file_request = Some(FileRequest::Duplicate { remove_completed });
+            state.filelist.reset();
+            state.filelist.set_title_text(match remove_completed {
+                true => "Duplicate Journal (without completed tasks):",
+                false => "Duplicate Journal:",
+            });
+            state.filelist.set_prompt_text("Duplicate As:");
+        }
+        (KeyCode::Char('a'), KeyModifiers::ALT) => {
+            state.journal.alphabetical_projects = !state.journal.alphabetical_projects;
+            state.journal.sort_projects();
+            state.add_feedback(match state.journal.alphabetical_projects {
+                true => "Sorting projects by name",
+                false => "Sorting projects manually",
+            });
+        }
+        (KeyCode::Char('l'), KeyModifiers::ALT) => {
+            if let Some(project) = state.journal.projects.selected() {
+                let selected = project
+                    .color
+                    .as_ref()
+                    .and_then(|color| TASK_COLORS.iter().position(|c| c == color))
+                    .map_or(0, |i| i + 1);
+                let labels: Vec<&str> = ["none"].into_iter().chain(TASK_COLORS).collect();
+                state.choice.set_options("Project Color", &labels, selected);
+                state.choice_request = Some(ChoiceRequest::ProjectColor);
+            }
+        }
+        (KeyCode::Char('x'), KeyModifiers::ALT) => {
+            let labels: Vec<&str> = ExportFormat::ALL.iter().map(|f| f.label()).collect();
+            state.choice.set_options("Export Format", &labels, 0);
+            state.choice_request = Some(ChoiceRequest::ExportFormat);
+        }
+        (KeyCode::Char('j' | 'J'), modifiers) if modifiers.contains(KeyModifiers::ALT) => {
+            let merge = modifiers.contains(KeyModifiers::SHIFT);
+            state.file_request = Some(FileRequest::ImportJson { merge });
+            state.filelist.reset_exports();
+            state.filelist.set_title_text(match merge {
+                true => "Merge JSON:",
+                false => "Import JSON:",
+            });
+            state.filelist.set_prompt_text("");
+        }
+        (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+            state.file_request = Some(FileRequest::Checkpoint);
+            state.filelist.reset_checkpoints(&filename(&state.filepath));
+            state.filelist.set_title_text("Restore Checkpoint:");
+            state.filelist.set_prompt_text("New Checkpoint Label:");
+        }
+        (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
+            state.file_request = Some(FileRequest::Template);
+            state.filelist.reset_templates();
+            state.filelist.set_title_text("New From Template:");
+            state.filelist.set_prompt_text("Save Journal As Template:");
+        }
         (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-            return match save_state(state, None) {
-                Err(e) => state.add_feedback(Error::from_cause("Failed to save file", e)),
-                Ok(_) => {
-                    state.add_feedback(format!("Saved journal `{}`", filename(&state.filepath)))
+            match state.filepath.exists() && file_modified(&state.filepath) != state.file_modified {
+                true => {
+                    let labels = ["Reload from disk", "Overwrite"];
+                    state.choice.set_options("Changed on disk", &labels, 0);
+                    state.choice_request = Some(ChoiceRequest::ChangedOnDisk);
                 }
-            };
+                false => save_with_feedback(state),
+            }
         }
         // Other
         (KeyCode::Char(c), _) => {
-            // Navigation (project by number key)
+            // A count prefix for motions, or else a project number, see `handle_event`
             if let Some(digit) = c.to_digit(10) {
-                state.journal.projects.select(digit as usize - 1).ok();
+                state.count_prefix = Some(count.unwrap_or(0) * 10 + digit as usize);
             };
         }
         _ => (),
     };
 }
 
-fn move_task(state: &mut App, to_prev: bool) {
+/// Keys that operate on the journal or project list rather than the contents of a project
+/// `n` and `N` jump between matches instead of adding while filtering
+fn is_filter_jump(key: KeyEvent, state: &App) -> bool {
+    let filtering = state
+        .journal
+        .projects
+        .selected()
+        .is_some_and(|p| p.filter.is_some());
+    filtering
+        && matches!(
+            (key.code, key.modifiers),
+            (KeyCode::Char('n'), KeyModifiers::NONE) | (KeyCode::Char('N'), KeyModifiers::SHIFT)
+        )
+}
+
+/// Why a key of `handle_journal_event` would do nothing, for lack of a selected target
+fn missing_target(key: KeyEvent, state: &App) -> Option<&'static str> {
+    if is_filter_jump(key, state) {
+        return None;
+    }
+    let project = state.journal.projects.selected();
+    let subproject = project.and_then(|p| p.subprojects.selected());
+    let task = subproject.and_then(|s| s.tasks.selected());
+    let needs_task = matches!(
+        (key.code, key.modifiers),
+        (
+            KeyCode::Char(' ' | 'r' | 'c' | 'u' | 'p' | 'd' | 'i' | 'm' | 'o'),
+            KeyModifiers::NONE
+        ) | (KeyCode::Char('P'), KeyModifiers::SHIFT)
+            | (
+                KeyCode::Up
+                    | KeyCode::Down
+                    | KeyCode::Left
+                    | KeyCode::Right
+                    | KeyCode::Home
+                    | KeyCode::End,
+                KeyModifiers::CONTROL
+            )
+    );
+    let needs_subproject = needs_task
+        || matches!(
+            (key.code, key.modifiers),
+            (KeyCode::Char('n' | 'g'), KeyModifiers::NONE)
+                | (
+                    KeyCode::Char('A' | 'R' | 'D' | 'G' | 'M' | 'S' | 'Y' | 'X' | 'U'),
+                    KeyModifiers::SHIFT
+                )
+                | (KeyCode::Char('x' | 'y'), KeyModifiers::CONTROL)
+                | (
+                    KeyCode::Left | KeyCode::Right | KeyCode::PageUp | KeyCode::PageDown,
+                    KeyModifiers::SHIFT
+                )
+                | (KeyCode::Left | KeyCode::Right, KeyModifiers::ALT)
+        );
+    let needs_project = needs_subproject
+        || matches!(
+            (key.code, key.modifiers),
+            (KeyCode::Char('N'), KeyModifiers::SHIFT)
+                | (
+                    KeyCode::Char('r' | 'd' | 'e' | 'p' | 'l' | 'y' | 'v'),
+                    KeyModifiers::ALT
+                )
+        );
+    if needs_project && project.is_none() {
+        Some("No project selected")
+    } else if needs_subproject && subproject.is_none() {
+        Some("No subproject selected")
+    } else if needs_task && task.is_none() {
+        Some("No task selected")
+    } else {
+        None
+    }
+}
+
+/// Keys of `handle_journal_event` that change the journal or write files other than exports
+fn is_mutating_key(key: KeyEvent, state: &App) -> bool {
+    if is_filter_jump(key, state) {
+        return false;
+    }
+    let task_selected = state.journal.selected_task().is_some();
+    matches!(
+        (key.code, key.modifiers),
+        (
+            KeyCode::Char(
+                'n' | 'r' | 'd' | ' ' | 'c' | 'u' | 'p' | '=' | '-' | '\\' | 'z' | 'a' | 'v'
+            ),
+            KeyModifiers::NONE
+        ) | (
+            KeyCode::Char('N' | 'A' | 'R' | 'P' | 'D' | 'O' | 'M' | 'S' | 'X' | 'U'),
+            KeyModifiers::SHIFT
+        ) | (
+            KeyCode::Char('n' | 'r' | 'd' | 'p' | 's' | 'i' | 'j' | 'J' | 'l' | 'v' | 'a'),
+            KeyModifiers::ALT
+        ) | (
+            KeyCode::Char('r' | 'p' | 't' | 's' | 'k' | 'x'),
+            KeyModifiers::CONTROL
+        ) | (
+            KeyCode::PageDown | KeyCode::PageUp | KeyCode::Home | KeyCode::End,
+            KeyModifiers::ALT
+        ) | (
+            KeyCode::Left | KeyCode::Right | KeyCode::PageDown | KeyCode::PageUp,
+            KeyModifiers::SHIFT | KeyModifiers::ALT
+        ) | (
+            KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Left
+                | KeyCode::Right
+                | KeyCode::Home
+                | KeyCode::End,
+            KeyModifiers::CONTROL
+        )
+    ) || (task_selected && matches!(key.code, KeyCode::Tab | KeyCode::BackTab))
+        || (matches!(key.code, KeyCode::Char('c' | 'C'))
+            && key.modifiers.contains(KeyModifiers::ALT))
+}
+
+/// Keys that take a count prefix, see `handle_event`
+fn takes_count(key: KeyEvent) -> bool {
+    matches!(
+        (key.code, key.modifiers),
+        (
+            KeyCode::Right
+                | KeyCode::Left
+                | KeyCode::Down
+                | KeyCode::Up
+                | KeyCode::Char('h' | 'j' | 'k' | 'l'),
+            KeyModifiers::NONE
+        ) | (
+            KeyCode::Right | KeyCode::Left | KeyCode::Home,
+            KeyModifiers::ALT
+        )
+    )
+}
+
+fn is_digit(key: KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char(c) if c.is_ascii_digit())
+}
+
+/// Select a project by its position, counting from one
+fn select_project_number(state: &mut App, number: usize) {
+    if let Some(index) = number.checked_sub(1) {
+        state.journal.projects.select(index).ok();
+    }
+}
+
+fn is_journal_key(key: KeyEvent) -> bool {
+    matches!(
+        (key.code, key.modifiers),
+        (KeyCode::Tab, KeyModifiers::NONE)
+            | (KeyCode::BackTab, _)
+            | (
+                KeyCode::PageDown | KeyCode::PageUp,
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+            | (KeyCode::Home | KeyCode::End, KeyModifiers::ALT)
+            | (
+                KeyCode::Char('n' | 'r' | 'd' | 's' | 'e' | 'i' | 'c' | 'x' | 'j' | 'l' | 'a'),
+                KeyModifiers::ALT
+            )
+            | (KeyCode::Char('J'), _)
+            | (KeyCode::Char('C'), _)
+            | (
+                KeyCode::Char('r' | 'p' | 'o' | 's' | 'b' | 't' | 'k' | 'g'),
+                KeyModifiers::CONTROL
+            )
+            | (KeyCode::Char('O'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('0'..='9'), _)
+    )
+}
+
+fn handle_locked_project_event(key: KeyEvent, state: &mut App) {
     if let Some(project) = state.journal.project() {
-        if let Some(subproject) = project.subproject() {
-            if let Some(task) = subproject.tasks.pop_selected() {
+        match (key.code, key.modifiers) {
+            (KeyCode::Enter, KeyModifiers::NONE) => {
+                let prompt_text = format!("Password for `{}`:", project.name);
+                set_project_prompt(
+                    project,
+                    JournalPrompt::UnlockProject,
+                    &prompt_text,
+                    "",
+                    true,
+                );
+            }
+            _ => state.add_feedback("Project is locked, press Enter to unlock"),
+        }
+    }
+}
+
+fn navigate(state: &mut App, motion: Motion, count: Option<usize>) {
+    let repeat = count.unwrap_or(1).max(1);
+    let wrap = state.settings.wrap_navigation;
+    match motion {
+        Motion::Left | Motion::Right if state.focus == FocusRegion::Tabs => {
+            for _ in 0..repeat {
+                match motion {
+                    Motion::Right => state.journal.projects.step_next(wrap),
+                    _ => state.journal.projects.step_prev(wrap),
+                }
+            }
+            return;
+        }
+        Motion::Up | Motion::Down => state.focus = FocusRegion::Tasks,
+        _ => (),
+    }
+    if let Some(project) = state.journal.project() {
+        for _ in 0..repeat {
+            match motion {
+                Motion::Right => project.subprojects.step_next(wrap),
+                Motion::Left => project.subprojects.step_prev(wrap),
+                Motion::Down => {
+                    if let Some(subproject) = project.subproject() {
+                        subproject.tasks.step_next(wrap);
+                    }
+                }
+                Motion::Up => {
+                    if let Some(subproject) = project.subproject() {
+                        subproject.tasks.step_prev(wrap);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Focus the next region, selecting something to focus in it
+fn cycle_focus(state: &mut App) {
+    state.focus = state.focus.next();
+    let focus = state.focus;
+    if let Some(project) = state.journal.project() {
+        match focus {
+            FocusRegion::Tabs => (),
+            FocusRegion::Subprojects => {
+                if project.subprojects.selection().is_none() {
+                    project.subprojects.select(0).ok();
+                }
+                if let Some(subproject) = project.subproject() {
+                    subproject.tasks.deselect();
+                }
+            }
+            FocusRegion::Tasks => {
+                if let Some(subproject) = project.subproject() {
+                    if subproject.tasks.selection().is_none() {
+                        subproject.tasks.select(0).ok();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn move_task(state: &mut App, to_prev: bool) {
+    if let Some(project) = state.journal.project() {
+        if let Some(subproject) = project.subproject() {
+            if let Some(task) = subproject.tasks.pop_selected() {
                 let target_subproject = match to_prev {
                     true => project
                         .subprojects
@@ -308,6 +1283,7 @@ fn move_task(state: &mut App, to_prev: bool) {
                     task,
                     true,
                 );
+                target_subproject.sort_tasks();
                 match to_prev {
                     true => project.subprojects.select_prev(),
                     false => project.subprojects.select_next(),
@@ -317,6 +1293,83 @@ fn move_task(state: &mut App, to_prev: bool) {
     }
 }
 
+/// Move the marked tasks to the end of an adjacent subproject, or of the subproject numbered by
+/// the count prefix
+fn move_marked_tasks(state: &mut App, to_prev: bool, count: Option<usize>) {
+    let Some(project) = state.journal.project() else {
+        return;
+    };
+    let target = match count {
+        Some(count) => count
+            .checked_sub(1)
+            .filter(|index| *index < project.subprojects.len()),
+        None if to_prev => project.subprojects.prev_index(),
+        None => project.subprojects.next_index(),
+    };
+    let Some(target) = target else {
+        return state.add_feedback(Error::from("No such subproject"));
+    };
+    if Some(target) == project.subprojects.selection() {
+        return state.add_feedback(Error::from("Tasks are already in that subproject"));
+    }
+    let Some(tasks) = project.subproject().map(|s| s.pop_marked()) else {
+        return;
+    };
+    if tasks.is_empty() {
+        return state.add_feedback(Error::from("No tasks marked"));
+    }
+    let moved = tasks.len();
+    let target_subproject = project
+        .subprojects
+        .get_item_mut(Some(target))
+        .expect("target index should be in range");
+    tasks
+        .into_iter()
+        .for_each(|task| target_subproject.tasks.push_item(task));
+    target_subproject.sort_tasks();
+    project.subprojects.select(target).ok();
+    bind_focus_size(project, &state.settings);
+    state.add_feedback(format!("Moved {moved} tasks"));
+}
+
+fn move_subproject(state: &mut App, to_prev: bool) {
+    let projects = &mut state.journal.projects;
+    let (Some(source), Some(target)) = (
+        projects.selection(),
+        match to_prev {
+            true => projects.prev_index(),
+            false => projects.next_index(),
+        },
+    ) else {
+        return;
+    };
+    if source == target {
+        return;
+    }
+    if projects
+        .get_item(Some(target))
+        .is_none_or(|p| p.is_locked())
+    {
+        return state.add_feedback(Error::from("Cannot move into a locked project"));
+    }
+    let Some(subproject) = projects
+        .get_item_mut(Some(source))
+        .and_then(|project| project.subprojects.pop_selected())
+    else {
+        return;
+    };
+    let target_project = projects
+        .get_item_mut(Some(target))
+        .expect("target index should be in range");
+    target_project.subprojects.insert_item(
+        target_project.subprojects.selection(),
+        subproject,
+        true,
+    );
+    bind_focus_size(target_project, &state.settings);
+    projects.select(target).ok();
+}
+
 fn handle_app_prompt_event(key: KeyEvent, state: &mut App) {
     let request = state
         .prompt_request
@@ -324,13 +1377,40 @@ fn handle_app_prompt_event(key: KeyEvent, state: &mut App) {
         .expect("should not be handling prompt events without a request");
     match state.prompt.handle_event(key) {
         PromptEvent::Cancelled => {
-            state.prompt_request = None;
+            if !state.locked {
+                state.prompt_request = None;
+            }
         }
-        PromptEvent::AwaitingResult(_) => (),
+        PromptEvent::AwaitingResult => (),
+        PromptEvent::Feedback(message) => state.add_feedback(Error::from(message)),
         PromptEvent::Result(result_text) => {
+            let is_password = matches!(
+                request,
+                AppPrompt::Unlock
+                    | AppPrompt::LoadFile(_)
+                    | AppPrompt::MergeFile(_)
+                    | AppPrompt::ImportProject(_)
+            );
+            if let Some(wait) = state.password_wait().filter(|_| is_password) {
+                return state.add_feedback(Feedback::warning(&format!(
+                    "Wait {}s before trying again",
+                    wait.as_secs() + 1
+                )));
+            }
             state.prompt.clear();
             state.prompt_request = None;
             match request {
+                AppPrompt::Unlock => match *result_text == *state.journal.password {
+                    true => {
+                        state.password_succeeded();
+                        state.locked = false;
+                    }
+                    false => {
+                        state.password_failed();
+                        lock(state);
+                        state.add_feedback(Error::from("Wrong password"));
+                    }
+                },
                 AppPrompt::NewJournal => {
                     state.journal = Journal::new(&result_text);
                     state.filepath = state.datadir.join(result_text);
@@ -340,7 +1420,7 @@ fn handle_app_prompt_event(key: KeyEvent, state: &mut App) {
                         }
                         Ok(_) => {
                             if let Some(project) = state.journal.project() {
-                                reset_ui(project);
+                                reset_ui(project, &state.settings);
                             };
                             state.add_feedback(format!(
                                 "Created journal `{}`",
@@ -350,55 +1430,165 @@ fn handle_app_prompt_event(key: KeyEvent, state: &mut App) {
                     }
                 }
                 AppPrompt::LoadFile(name) => match load_state(state, &name, &result_text, false) {
-                    Err(e) => state.add_feedback(Error::from_cause("Failed to load file", e)),
-                    Ok(_) => state
-                        .add_feedback(format!("Loaded journal `{}`", filename(&state.filepath))),
+                    Err(e) => {
+                        if let LoadError::Decrypt = e {
+                            state.password_failed();
+                        }
+                        state.goto = None;
+                        state.add_feedback(Error::from_cause("Failed to load file", e.into()));
+                    }
+                    Ok(_) => {
+                        state.password_succeeded();
+                        state.add_feedback(format!(
+                            "Loaded journal `{}`",
+                            filename(&state.filepath)
+                        ));
+                        goto_pending(state);
+                    }
                 },
                 AppPrompt::MergeFile(name) => match load_state(state, &name, &result_text, true) {
-                    Err(e) => state.add_feedback(Error::from_cause("Failed to merge file", e)),
+                    Err(e) => {
+                        if let LoadError::Decrypt = e {
+                            state.password_failed();
+                        }
+                        state.add_feedback(Error::from_cause("Failed to merge file", e.into()));
+                    }
+                    Ok(_) => {
+                        state.password_succeeded();
+                        state.add_feedback(format!(
+                            "Merged journal `{}`",
+                            filename(&state.filepath)
+                        ));
+                    }
+                },
+                AppPrompt::NewFromJson(json_name) => {
+                    match new_from_json(state, &json_name, &result_text) {
+                        Err(e) => state.add_feedback(Error::from_cause("Failed to import JSON", e)),
+                        Ok(_) => state.add_feedback(format!(
+                            "Created journal `{}` from `{json_name}`",
+                            filename(&state.filepath)
+                        )),
+                    }
+                }
+                AppPrompt::NewFromTemplate(template) => {
+                    match new_from_template(state, &template, &result_text) {
+                        Err(e) => state.add_feedback(Error::from_cause(
+                            "Failed to create journal from template",
+                            e,
+                        )),
+                        Ok(_) => state.add_feedback(format!(
+                            "Created journal `{}` from template `{template}`",
+                            filename(&state.filepath)
+                        )),
+                    }
+                }
+                AppPrompt::RenameFile => match rename_file(state, &result_text) {
+                    Err(e) => state.add_feedback(Error::from_cause("Failed to rename file", e)),
                     Ok(_) => state
-                        .add_feedback(format!("Merged journal `{}`", filename(&state.filepath))),
+                        .add_feedback(format!("Renamed file to `{}`", filename(&state.filepath))),
+                },
+                AppPrompt::GoToProject => match state.journal.find_project(&result_text) {
+                    Some(index) => state.journal.projects.select(index).unwrap_or_default(),
+                    None => state
+                        .add_feedback(Error::from(format!("No project matching `{result_text}`"))),
                 },
+                AppPrompt::ImportProject(name) => {
+                    match import_project(state, &name, &result_text) {
+                        Err(e) => {
+                            if let LoadError::Decrypt = e {
+                                state.password_failed();
+                            }
+                            state.add_feedback(Error::from_cause(
+                                "Failed to import project",
+                                e.into(),
+                            ))
+                        }
+                        Ok(_) => {
+                            state.password_succeeded();
+                            state.add_feedback(format!("Imported project from `{name}`"))
+                        }
+                    }
+                }
             };
         }
     }
 }
 
 fn handle_journal_prompt_event(key: KeyEvent, state: &mut App) {
+    let password_wait = state.password_wait();
     if let Some(project) = state.journal.project() {
-        if let Some(request) = project.prompt_request.clone() {
+        // Borrowed rather than cloned, as it may hold a password
+        if let Some(request) = project.prompt_request.as_ref() {
             match project.prompt.handle_event(key) {
-                PromptEvent::Cancelled => project.prompt_request = None,
-                PromptEvent::AwaitingResult(_) => (),
-                PromptEvent::Result(result_text) => {
+                PromptEvent::Cancelled => {
                     project.prompt.clear();
                     project.prompt_request = None;
+                }
+                PromptEvent::AwaitingResult => (),
+                PromptEvent::Feedback(message) => state.add_feedback(Error::from(message)),
+                PromptEvent::Result(mut result_text) => {
+                    if request.is_name() {
+                        result_text = normalize_name(&result_text);
+                    }
+                    // Keep the prompt open for another try
+                    let is_blank = match request.multiline() {
+                        true => result_text.lines().all(|l| normalize_name(l).is_empty()),
+                        false => request.is_name() && result_text.is_empty(),
+                    };
+                    if is_blank {
+                        return state.add_feedback(Feedback::warning("Name cannot be empty"));
+                    }
+                    if let (JournalPrompt::UnlockProject, Some(wait)) = (request, password_wait) {
+                        return state.add_feedback(Feedback::warning(&format!(
+                            "Wait {}s before trying again",
+                            wait.as_secs() + 1
+                        )));
+                    }
+                    project.prompt.clear();
+                    let Some(request) = project.prompt_request.take() else {
+                        return;
+                    };
                     match request {
                         JournalPrompt::AddProject => {
                             state
                                 .journal
                                 .projects
                                 .add_item(Project::new(&result_text), true);
+                            state.journal.sort_projects();
                         }
                         JournalPrompt::AddSubProject => {
                             project
                                 .subprojects
                                 .add_item(SubProject::new(&result_text), true);
-                            bind_focus_size(project);
+                            bind_focus_size(project, &state.settings);
                         }
                         JournalPrompt::AddTask => {
                             if let Some(subproject) = project.subproject() {
                                 subproject.tasks.add_item(Task::new(&result_text), true);
+                                subproject.sort_tasks();
+                            }
+                        }
+                        JournalPrompt::AddTasksBulk => {
+                            if let Some(subproject) = project.subproject() {
+                                let lines = result_text.lines().map(normalize_name);
+                                for line in lines.filter(|line| !line.is_empty()) {
+                                    subproject.tasks.add_item(Task::new(&line), true);
+                                }
+                                subproject.sort_tasks();
                             }
                         }
                         JournalPrompt::RenameJournal => {
                             state.journal.name = result_text;
-                            return state
-                                .add_feedback(format!("Renamed journal: {}", state.journal.name));
+                            state.add_feedback(format!("Renamed journal: {}", state.journal.name));
                         }
                         JournalPrompt::RenameProject => {
                             project.name = result_text.clone();
-                            return state.add_feedback(format!("Renamed project: {result_text}",));
+                            state.journal.sort_projects();
+                            state.add_feedback(format!("Renamed project: {result_text}",));
+                        }
+                        JournalPrompt::SaveLayout => {
+                            project.save_layout(&result_text);
+                            state.add_feedback(format!("Saved layout: {result_text}"));
                         }
                         JournalPrompt::RenameSubProject => {
                             if let Some(subproject) = project.subproject() {
@@ -410,12 +1600,112 @@ fn handle_journal_prompt_event(key: KeyEvent, state: &mut App) {
                                 if let Some(task) = subproject.task() {
                                     task.desc = result_text;
                                 }
+                                subproject.sort_tasks();
+                            }
+                        }
+                        JournalPrompt::Filter => {
+                            project.filter = match result_text.is_empty() {
+                                true => None,
+                                false => match TaskFilter::new(&result_text) {
+                                    Err(e) => {
+                                        return state
+                                            .add_feedback(Error::from_cause("Invalid regex", e))
+                                    }
+                                    Ok(filter) => Some(filter),
+                                },
+                            };
+                        }
+                        JournalPrompt::SetDueDate => {
+                            let due_at = result_text.trim();
+                            if !due_at.is_empty()
+                                && NaiveDate::parse_from_str(due_at, DATE_FORMAT).is_err()
+                            {
+                                return state.add_feedback(format!(
+                                    "Invalid date `{due_at}`, use YYYY-MM-DD"
+                                ));
+                            }
+                            if let Some(subproject) = project.subproject() {
+                                if let Some(task) = subproject.task() {
+                                    task.due_at = Some(due_at.to_owned()).filter(|d| !d.is_empty());
+                                    task.reminded = false;
+                                }
+                                subproject.sort_tasks();
                             }
                         }
                         JournalPrompt::SetPassword => {
-                            state.journal.password = result_text;
+                            set_project_prompt(
+                                project,
+                                JournalPrompt::ConfirmPassword(Password::from(result_text)),
+                                "Confirm password:",
+                                "",
+                                true,
+                            );
+                        }
+                        JournalPrompt::ConfirmPassword(password) => {
+                            let confirmed = Password::from(result_text);
+                            if password != confirmed {
+                                set_project_prompt(
+                                    project,
+                                    JournalPrompt::SetPassword,
+                                    "Set new password (again):",
+                                    "",
+                                    true,
+                                );
+                                return state.add_feedback(Error::from("Passwords do not match"));
+                            }
+                            state.journal.password = confirmed;
                             state.add_feedback("Set encryption password");
                         }
+                        JournalPrompt::SetProjectPassword => {
+                            set_project_prompt(
+                                project,
+                                JournalPrompt::ConfirmProjectPassword(Password::from(result_text)),
+                                "Confirm project password:",
+                                "",
+                                true,
+                            );
+                        }
+                        JournalPrompt::ConfirmProjectPassword(password) => {
+                            let confirmed = Password::from(result_text);
+                            if password != confirmed {
+                                set_project_prompt(
+                                    project,
+                                    JournalPrompt::SetProjectPassword,
+                                    "Set new project password (again):",
+                                    "",
+                                    true,
+                                );
+                                return state.add_feedback(Error::from("Passwords do not match"));
+                            }
+                            project.password = confirmed;
+                            match project.password.is_empty() {
+                                true => state.add_feedback("Removed project password"),
+                                false => state.add_feedback("Set project password"),
+                            }
+                        }
+                        JournalPrompt::UnlockProject => {
+                            let locked_fingerprint = project.fingerprint();
+                            match project.unlock(&Password::from(result_text)) {
+                                Err(e) => {
+                                    if let LoadError::Decrypt = e {
+                                        state.password_failed();
+                                    }
+                                    state.add_feedback(Error::from_cause(
+                                        "Failed to unlock project",
+                                        e.into(),
+                                    ))
+                                }
+                                Ok(_) => {
+                                    // Unlocking alone is not an unsaved change
+                                    state.saved_fingerprint = state
+                                        .saved_fingerprint
+                                        .wrapping_sub(locked_fingerprint)
+                                        .wrapping_add(project.fingerprint());
+                                    state.password_succeeded();
+                                    state.add_feedback("Unlocked project");
+                                }
+                            }
+                        }
                     };
                 }
             };
@@ -423,15 +1713,156 @@ fn handle_journal_prompt_event(key: KeyEvent, state: &mut App) {
     }
 }
 
+fn handle_confirm_event(key: KeyEvent, state: &mut App, request: ConfirmRequest) {
+    match state.confirm.handle_event(key, &state.settings) {
+        ConfirmEvent::AwaitingResult => return,
+        ConfirmEvent::Cancelled | ConfirmEvent::No => (),
+        ConfirmEvent::Yes => match request {
+            ConfirmRequest::DeleteProject => {
+                state.journal.projects.pop_selected();
+            }
+            ConfirmRequest::DeleteSubProject => {
+                if let Some(project) = state.journal.project() {
+                    project.subprojects.pop_selected();
+                }
+            }
+            ConfirmRequest::ClearCompleted => clear_completed(state),
+            ConfirmRequest::EmptyTrash => {
+                let message = state.filelist.empty_trash();
+                state.add_feedback(message);
+            }
+            ConfirmRequest::Quit => state.quit = true,
+        },
+    }
+    state.confirm_request = None;
+}
+
+fn clear_completed(state: &mut App) {
+    if let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) {
+        let count = subproject.clear_completed();
+        state.add_feedback(format!("Cleared {count} completed tasks"));
+    }
+}
+
+fn handle_choice_event(key: KeyEvent, state: &mut App, request: ChoiceRequest) {
+    let index = match state.choice.handle_event(key) {
+        ChoiceEvent::AwaitingResult => return,
+        ChoiceEvent::Cancelled => return state.choice_request = None,
+        ChoiceEvent::Result(index) => index,
+    };
+    state.choice_request = None;
+    if let ChoiceRequest::ExportFormat = request {
+        let format = ExportFormat::ALL[index];
+        state.file_request = Some(FileRequest::Export(format));
+        state.filelist.reset_exports();
+        state
+            .filelist
+            .set_title_text(&format!("Export {}:", format.label()));
+        state.filelist.set_prompt_text("Export As:");
+        return;
+    }
+    if let ChoiceRequest::ChangedOnDisk = request {
+        match index {
+            0 => reload_state(state),
+            _ => save_with_feedback(state),
+        }
+        return;
+    }
+    if let ChoiceRequest::Url = request {
+        let url = state
+            .journal
+            .selected_task()
+            .and_then(|task| task.urls().get(index).map(|url| url.to_string()));
+        if let Some(url) = url {
+            open_url(state, &url);
+        }
+        return;
+    }
+    if let ChoiceRequest::ProjectColor = request {
+        if let Some(project) = state.journal.project() {
+            project.color = index.checked_sub(1).map(|i| TASK_COLORS[i].to_owned());
+        }
+        return;
+    }
+    let Some(subproject) = state.journal.project().and_then(|p| p.subproject()) else {
+        return;
+    };
+    if let ChoiceRequest::SortKey = request {
+        subproject.sort_key = index.checked_sub(1).map(|i| TaskSortKey::ALL[i]);
+        return subproject.sort_tasks();
+    }
+    if let Some(task) = subproject.task() {
+        match request {
+            ChoiceRequest::Priority => task.priority = index as u8,
+            ChoiceRequest::Color => {
+                task.color = index.checked_sub(1).map(|i| TASK_COLORS[i].to_owned());
+            }
+            ChoiceRequest::ChangedOnDisk
+            | ChoiceRequest::ExportFormat
+            | ChoiceRequest::SortKey
+            | ChoiceRequest::Url
+            | ChoiceRequest::ProjectColor => (),
+        }
+    }
+    subproject.sort_tasks();
+}
+
+fn handle_overlay_event(key: KeyEvent, state: &mut App, overlay: Overlay) {
+    let close = match overlay {
+        Overlay::TaskInfo => matches!(key.code, KeyCode::Char('i')),
+        Overlay::Stats => matches!(key.code, KeyCode::F(4)),
+        Overlay::Activity => matches!(key.code, KeyCode::F(5)),
+        Overlay::Today => matches!(key.code, KeyCode::F(6)),
+    };
+    if close || key.code == KeyCode::Esc {
+        state.overlay = None;
+    } else if let Overlay::Today = overlay {
+        handle_today_event(key, state);
+    } else if key.code == KeyCode::Char('t') {
+        state.settings.relative_timestamps = !state.settings.relative_timestamps;
+    }
+}
+
+fn handle_today_event(key: KeyEvent, state: &mut App) {
+    match key.code {
+        KeyCode::Down | KeyCode::Char('j') => state.today.select_next(),
+        KeyCode::Up | KeyCode::Char('k') => state.today.select_prev(),
+        KeyCode::Enter => {
+            if let Some(location) = state.today.selected().copied() {
+                match state.journal.select_location(location) {
+                    Ok(()) => state.focus = FocusRegion::Tasks,
+                    Err(e) => state.add_feedback(Error::from_cause("Task not found", e)),
+                }
+                state.overlay = None;
+            }
+        }
+        _ => (),
+    }
+}
+
 fn handle_filelist_event(key: KeyEvent, state: &mut App) {
     match state.filelist.handle_event(key) {
         FileListResult::AwaitingResult => (),
         FileListResult::Cancelled => state.file_request = None,
         FileListResult::Feedback(message) => state.add_feedback(message),
+        FileListResult::EmptyTrash(count) => {
+            state
+                .confirm
+                .set_message(&format!("Permanently delete {count} files in the trash?"));
+            state.confirm_request = Some(ConfirmRequest::EmptyTrash);
+        }
         FileListResult::Result(name) => {
             if let Some(fr) = state.file_request {
                 state.file_request = None;
                 match fr {
+                    FileRequest::Load | FileRequest::LoadMerge
+                        if state.datadir.join(&name).exists()
+                            && !is_journal_file(&state.datadir.join(&name)) =>
+                    {
+                        state.add_feedback(Error::from(format!(
+                            "`{name}` is not a devjournal file"
+                        )));
+                    }
                     FileRequest::Load => set_app_prompt(
                         state,
                         AppPrompt::LoadFile(name.clone()),
@@ -448,7 +1879,7 @@ fn handle_filelist_event(key: KeyEvent, state: &mut App) {
                     ),
                     FileRequest::Save => {
                         let filepath = state.datadir.join(name);
-                        return match save_state(state, Some(&filepath)) {
+                        match save_state(state, Some(&filepath)) {
                             Err(e) => {
                                 state.add_feedback(Error::from_cause("Failed to save file", e))
                             }
@@ -456,6 +1887,81 @@ fn handle_filelist_event(key: KeyEvent, state: &mut App) {
                                 .add_feedback(format!("Saved journal `{}`", filename(&filepath))),
                         };
                     }
+                    FileRequest::Duplicate { remove_completed } => {
+                        let filepath = state.datadir.join(name);
+                        match duplicate_journal(state, &filepath, remove_completed) {
+                            Err(e) => state
+                                .add_feedback(Error::from_cause("Failed to duplicate journal", e)),
+                            Ok(_) => state.add_feedback(format!(
+                                "Duplicated journal to `{}`",
+                                filename(&filepath)
+                            )),
+                        };
+                    }
+                    FileRequest::Template => {
+                        match state.datadir.join(TEMPLATES_DIR).join(&name).exists() {
+                            true => set_app_prompt(
+                                state,
+                                AppPrompt::NewFromTemplate(name),
+                                "New file name:",
+                                "",
+                                false,
+                            ),
+                            false => match save_template(state, &name) {
+                                Err(e) => state
+                                    .add_feedback(Error::from_cause("Failed to save template", e)),
+                                Ok(_) => state.add_feedback(format!("Saved template `{name}`")),
+                            },
+                        }
+                    }
+                    FileRequest::Checkpoint => {
+                        let result = match checkpoint_path(state, &name).exists() {
+                            true => restore_checkpoint(state, &name)
+                                .map(|_| format!("Restored checkpoint `{name}`, not yet saved")),
+                            false => save_checkpoint(state, &name)
+                                .map(|_| format!("Saved checkpoint `{name}`")),
+                        };
+                        match result {
+                            Err(e) => state.add_feedback(Error::from_cause("Checkpoint failed", e)),
+                            Ok(message) => state.add_feedback(message),
+                        }
+                    }
+                    FileRequest::Export(format) => match write_export(state, &name, format) {
+                        Err(e) => state.add_feedback(Error::from_cause("Failed to export", e)),
+                        Ok(filepath) => {
+                            state.add_feedback(format!("Exported to {}", filepath.display()))
+                        }
+                    },
+                    FileRequest::ImportJson { merge: false } => set_app_prompt(
+                        state,
+                        AppPrompt::NewFromJson(name),
+                        "New file name:",
+                        "",
+                        false,
+                    ),
+                    FileRequest::ImportJson { merge: true } => match merge_json(state, &name) {
+                        Err(e) => state.add_feedback(Error::from_cause("Failed to merge JSON", e)),
+                        Ok(_) => state.add_feedback(format!("Merged `{name}`")),
+                    },
+                    FileRequest::ExportProject => {
+                        let filepath = state.datadir.join(name);
+                        match export_project(state, &filepath) {
+                            Err(e) => {
+                                state.add_feedback(Error::from_cause("Failed to export project", e))
+                            }
+                            Ok(_) => state.add_feedback(format!(
+                                "Exported project to `{}`",
+                                filename(&filepath)
+                            )),
+                        };
+                    }
+                    FileRequest::ImportProject => set_app_prompt(
+                        state,
+                        AppPrompt::ImportProject(name.clone()),
+                        &format!("Password for `{name}`:"),
+                        "",
+                        true,
+                    ),
                 }
             }
         }
@@ -484,64 +1990,1463 @@ fn set_project_prompt(
 ) {
     project.prompt.set_prompt_text(prompt_text);
     project.prompt.set_text(prefill_text);
+    project.prompt.set_max_length(request.max_length());
+    project.prompt.set_multiline(request.multiline());
     project.prompt_request = Some(request);
     project.prompt.set_password(password);
 }
 
-fn reset_ui(project: &mut Project) {
+fn reset_ui(project: &mut Project, settings: &Settings) {
     project.focused_width_percent = DEFAULT_WIDTH_PERCENT;
-    bind_focus_size(project);
+    bind_focus_size(project, settings);
 }
 
-fn bind_focus_size(project: &mut Project) {
-    let min_width = (100. / project.subprojects.len() as f32).max(5.) as u16;
-    project.focused_width_percent = project.focused_width_percent.min(95).max(min_width);
+fn bind_focus_size(project: &mut Project, settings: &Settings) {
+    let min_width = (100. / project.subprojects.len().max(1) as f32)
+        .max(settings.focus_width_min as f32) as u16;
+    project.focused_width_percent = project
+        .focused_width_percent
+        .min(settings.focus_width_max)
+        .max(min_width);
 }
 
 fn open_datadir(state: &App) -> Result<()> {
-    Command::new("xdg-open")
+    let command = state.settings.file_manager_command();
+    Command::new(command[0])
+        .args(&command[1..])
         .arg(&state.datadir)
         .spawn()
-        .map_err(Error::from)?;
+        .map_err(|e| Error::from(format!("cannot run `{}` ({e})", command[0])))?;
     Ok(())
 }
 
+fn open_url(state: &mut App, url: &str) {
+    let opener = platform_opener();
+    match Command::new(opener).arg(url).spawn() {
+        Ok(_) => state.add_feedback(format!("Opening {url}")),
+        Err(e) => state.add_feedback(Error::from(format!("Cannot run `{opener}` ({e})"))),
+    }
+}
+
+fn copy_to_clipboard(state: &mut App, text: String) -> Result<()> {
+    let clipboard = match &mut state.clipboard {
+        Some(clipboard) => clipboard,
+        None => state.clipboard.insert(arboard::Clipboard::new()?),
+    };
+    clipboard.set_text(text)?;
+    Ok(())
+}
+
+fn file_modified(filepath: &Path) -> Option<SystemTime> {
+    fs::metadata(filepath).and_then(|m| m.modified()).ok()
+}
+
+/// Replace the journal with the file on disk, discarding unsaved changes
+fn reload_state(state: &mut App) {
+    let name = filename(&state.filepath);
+    let key = state.journal.password.clone();
+    match load_state(state, &name, &key, false) {
+        Err(e) => state.add_feedback(Error::from_cause("Failed to reload file", e.into())),
+        Ok(_) => {
+            if let Some(project) = state.journal.project() {
+                reset_ui(project, &state.settings);
+            }
+            state.add_feedback(format!("Reloaded journal `{name}`"));
+        }
+    }
+}
+
+fn save_with_feedback(state: &mut App) {
+    match save_state(state, None) {
+        Err(e) => state.add_feedback(Feedback::critical(Error::from_cause(
+            "Failed to save file",
+            e,
+        ))),
+        Ok(_) => state.add_feedback(format!("Saved journal `{}`", filename(&state.filepath))),
+    };
+}
+
+/// The plaintext Markdown copy written next to a journal file
+fn mirror_path(filepath: &Path) -> PathBuf {
+    let mut mirror = filepath.as_os_str().to_owned();
+    mirror.push(MIRROR_SUFFIX);
+    PathBuf::from(mirror)
+}
+
 fn save_state(state: &mut App, filepath: Option<&PathBuf>) -> Result<()> {
     let filepath = filepath.unwrap_or(&state.filepath);
     state
         .journal
-        .save_encrypt(filepath, &state.journal.password)?;
+        .sealed(state.settings.encryption)?
+        .save_encrypt(filepath, &state.journal.password, state.settings.encryption)?;
+    if state.settings.plaintext_mirror {
+        fs::write(mirror_path(filepath), to_markdown(&state.journal))?;
+    }
     state.filepath = filepath.clone();
+    state.file_modified = file_modified(&state.filepath);
+    state.saved_fingerprint = state.journal.fingerprint();
+    state.settings.remember_journal(filename(&state.filepath));
+    state.filelist.reset();
+    Ok(())
+}
+
+/// Rename the journal file on disk along with its mirror and checkpoints, and the journal itself
+/// if it was named after the file
+fn rename_file(state: &mut App, name: &str) -> Result<()> {
+    let filepath = state.datadir.join(name);
+    if filepath == state.filepath {
+        return Ok(());
+    }
+    if name.is_empty() || filepath.parent() != Some(state.datadir.as_path()) {
+        return Error::from(format!("invalid file name `{name}`")).into();
+    }
+    if filepath.exists() {
+        return Error::from(format!("`{name}` already exists")).into();
+    }
+    let old_checkpoints = checkpoints_dir(state);
+    let checkpoints = state.datadir.join(CHECKPOINTS_DIR).join(name);
+    if old_checkpoints.exists() && checkpoints.exists() {
+        return Error::from(format!("checkpoints of `{name}` already exist")).into();
+    }
+    let old_name = filename(&state.filepath);
+    let old_mirror = mirror_path(&state.filepath);
+    match state.filepath.exists() {
+        true => fs::rename(&state.filepath, &filepath)?,
+        // Not saved yet, create it under the new name
+        false => save_state(state, Some(&filepath))?,
+    }
+    if old_mirror.exists() {
+        fs::rename(old_mirror, mirror_path(&filepath))?;
+    }
+    if old_checkpoints.exists() {
+        fs::rename(old_checkpoints, checkpoints)?;
+    }
+    if state.journal.name == old_name {
+        state.journal.name = name.to_owned();
+    }
+    state.filepath = filepath;
+    state.settings.remember_journal(name.to_owned());
     state.filelist.reset();
     Ok(())
 }
 
-fn load_state(state: &mut App, name: &str, key: &str, merge: bool) -> Result<()> {
+/// Save the journal as a template, without a password so it can be used from any journal
+fn save_template(state: &mut App, name: &str) -> Result<()> {
+    let templates = state.datadir.join(TEMPLATES_DIR);
+    fs::create_dir_all(&templates)?;
+    state
+        .journal
+        .sealed(state.settings.encryption)?
+        .save_encrypt(&templates.join(name), "", state.settings.encryption)?;
+    Ok(())
+}
+
+fn checkpoints_dir(state: &App) -> PathBuf {
+    state
+        .datadir
+        .join(CHECKPOINTS_DIR)
+        .join(filename(&state.filepath))
+}
+
+fn checkpoint_path(state: &App, label: &str) -> PathBuf {
+    checkpoints_dir(state).join(label)
+}
+
+/// Save the journal as a labeled checkpoint of its file, encrypted with the journal password
+fn save_checkpoint(state: &mut App, label: &str) -> Result<()> {
+    let dir = checkpoints_dir(state);
+    let filepath = dir.join(label);
+    if label.is_empty() || filepath.parent() != Some(dir.as_path()) {
+        return Error::from(format!("invalid label `{label}`")).into();
+    }
+    fs::create_dir_all(&dir)?;
+    state
+        .journal
+        .sealed(state.settings.encryption)?
+        .save_encrypt(
+            &filepath,
+            &state.journal.password,
+            state.settings.encryption,
+        )?;
+    Ok(())
+}
+
+/// Replace the journal with a checkpoint, keeping the journal file unchanged until saved
+fn restore_checkpoint(state: &mut App, label: &str) -> Result<()> {
+    let journal = Journal::load_decrypt(&checkpoint_path(state, label), &state.journal.password)?;
+    state.journal = journal;
+    if let Some(project) = state.journal.project() {
+        reset_ui(project, &state.settings);
+    };
+    Ok(())
+}
+
+fn new_from_template(state: &mut App, template: &str, name: &str) -> Result<()> {
+    let filepath = state.datadir.join(name);
+    if filepath.exists() {
+        return Error::from(format!("`{name}` already exists")).into();
+    }
+    let mut journal = Journal::load_decrypt(&state.datadir.join(TEMPLATES_DIR).join(template), "")?;
+    journal.reset_completion();
+    journal.name = name.to_owned();
+    state.journal = journal;
+    save_state(state, Some(&filepath))?;
+    if let Some(project) = state.journal.project() {
+        reset_ui(project, &state.settings);
+    };
+    Ok(())
+}
+
+fn new_from_json(state: &mut App, json_name: &str, name: &str) -> Result<()> {
+    let filepath = state.datadir.join(name);
+    if filepath.exists() {
+        return Error::from(format!("`{name}` already exists")).into();
+    }
+    let mut journal = load_json(&state.datadir.join(EXPORTS_DIR).join(json_name))?;
+    journal.name = name.to_owned();
+    state.journal = journal;
+    save_state(state, Some(&filepath))?;
+    if let Some(project) = state.journal.project() {
+        reset_ui(project, &state.settings);
+    };
+    Ok(())
+}
+
+fn merge_json(state: &mut App, json_name: &str) -> Result<()> {
+    let journal = load_json(&state.datadir.join(EXPORTS_DIR).join(json_name))?;
+    state.journal = state.journal.clone() + journal;
+    Ok(())
+}
+
+/// Write the journal in a plain format to the exports folder, returning the file path
+fn write_export(state: &App, name: &str, format: ExportFormat) -> Result<PathBuf> {
+    let exports = state.datadir.join(EXPORTS_DIR);
+    fs::create_dir_all(&exports)?;
+    let filepath = exports.join(name);
+    fs::write(&filepath, format.export(&state.journal)?)?;
+    Ok(filepath)
+}
+
+/// Save a copy of the journal to another file, keeping the current file open
+fn duplicate_journal(state: &mut App, filepath: &PathBuf, remove_completed: bool) -> Result<()> {
+    if filepath.exists() {
+        return Error::from(format!("`{}` already exists", filename(filepath))).into();
+    }
+    let mut journal = state.journal.clone();
+    if remove_completed {
+        journal.remove_completed();
+    }
+    journal.sealed(state.settings.encryption)?.save_encrypt(
+        filepath,
+        &state.journal.password,
+        state.settings.encryption,
+    )?;
+    state.filelist.reset();
+    Ok(())
+}
+
+/// Save the selected project on its own, encrypted with the journal password
+fn export_project(state: &mut App, filepath: &PathBuf) -> Result<()> {
+    let project = state
+        .journal
+        .projects
+        .selected()
+        .ok_or(Error::from("no project selected"))?;
+    project.sealed(state.settings.encryption)?.save_encrypt(
+        filepath,
+        &state.journal.password,
+        state.settings.encryption,
+    )?;
+    state.filelist.reset();
+    Ok(())
+}
+
+fn import_project(state: &mut App, name: &str, key: &str) -> std::result::Result<(), LoadError> {
+    let mut project = Project::load_decrypt(&state.datadir.join(name), key)?;
+    project.name = state.journal.unique_project_name(&project.name);
+    state.journal.projects.add_item(project, true);
+    state.journal.sort_projects();
+    if let Some(project) = state.journal.project() {
+        reset_ui(project, &state.settings);
+    }
+    Ok(())
+}
+
+fn load_state(
+    state: &mut App,
+    name: &str,
+    key: &str,
+    merge: bool,
+) -> std::result::Result<(), LoadError> {
     let filepath = state.datadir.join(name);
     if !filepath.exists() {
         Journal::new(name)
-            .save_encrypt(&filepath, key)
-            .map_err(|e| Error::from(format!("failed to create new file [{e}]")))?;
+            .save_encrypt(&filepath, key, state.settings.encryption)
+            .map_err(|e| LoadError::Io(Error::from(format!("failed to create new file [{e}]"))))?;
     }
     let loaded_journal = Journal::load_decrypt(&filepath, key)?;
     state.journal = match merge {
         true => state.journal.clone() + loaded_journal,
         false => loaded_journal,
     };
-    state.journal.password = key.to_owned();
+    state.journal.password = Password::from(key);
+    state.file_modified = file_modified(&filepath);
+    if !merge {
+        state.saved_fingerprint = state.journal.fingerprint();
+    }
     state.filepath = filepath;
+    state.settings.remember_journal(filename(&state.filepath));
     state.filelist.reset();
     Ok(())
 }
 
 pub fn try_load_file(state: &mut App, name: &str) {
-    if load_state(state, name, "", false).is_err() {
-        set_app_prompt(
+    let filepath = state.datadir.join(name);
+    if filepath.exists() && !is_journal_file(&filepath) {
+        return state.add_feedback(Error::from(format!("`{name}` is not a devjournal file")));
+    }
+    match load_state(state, name, "", false) {
+        Ok(_) => goto_pending(state),
+        Err(LoadError::Decrypt) => set_app_prompt(
             state,
             AppPrompt::LoadFile(name.to_owned()),
             &format!("Password for `{name}`:"),
             "",
             true,
+        ),
+        Err(e) => {
+            state.goto = None;
+            state.add_feedback(Error::from_cause(
+                &format!("Failed to load `{name}`"),
+                e.into(),
+            ));
+        }
+    }
+}
+
+/// Select the path given on the command line, once the journal it is in has loaded
+fn goto_pending(state: &mut App) {
+    if let Some(path) = state.goto.take() {
+        if let Err(e) = state.journal.select_path(&path) {
+            state.add_feedback(Error::from_cause(&format!("Cannot go to `{path}`"), e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::data::{timestamp_now, FORMAT_VERSION};
+    use crate::crypto::{encrypt, Algorithm};
+    use crate::testing::scratch_dir;
+    use std::time::Duration;
+    use tui::{backend::TestBackend, Terminal};
+
+    fn press(state: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+        handle_event(KeyEvent::new(code, modifiers), state);
+    }
+
+    /// A fresh journal whose first subproject has `count` tasks, the first one selected
+    fn with_tasks(count: usize) -> App<'static> {
+        let mut state = App::new(PathBuf::new());
+        let project = state.journal.project().unwrap();
+        project.subprojects.select(0).unwrap();
+        let subproject = project.subproject().unwrap();
+        for index in 0..count {
+            subproject
+                .tasks
+                .push_item(Task::new(&format!("Task {index}")));
+        }
+        subproject.tasks.select(0).ok();
+        state
+    }
+
+    fn task_count(state: &mut App) -> usize {
+        let project = state.journal.project().unwrap();
+        project
+            .subproject()
+            .map_or(0, |subproject| subproject.tasks.len())
+    }
+
+    fn task_selection(state: &mut App) -> Option<usize> {
+        state.journal.project()?.subproject()?.tasks.selection()
+    }
+
+    #[test]
+    fn count_prefix_repeats_motions() {
+        let mut state = with_tasks(5);
+        state
+            .journal
+            .projects
+            .add_item(Project::new("Second"), false);
+        press(&mut state, KeyCode::Char('3'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+        assert_eq!(task_selection(&mut state), Some(3));
+        // Without a motion, digits select a project by number, before the next key acts
+        press(&mut state, KeyCode::Char('2'), KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+        press(&mut state, KeyCode::Char('a'), KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selection(), Some(1));
+        assert!(state.journal.project().unwrap().show_ages);
+        // Or once no key follows in time
+        press(&mut state, KeyCode::Char('1'), KeyModifiers::NONE);
+        handle_tick(&mut state);
+        assert_eq!(state.journal.projects.selection(), Some(1));
+        state.last_input -= Duration::from_millis(COUNT_PREFIX_TIMEOUT_MS);
+        handle_tick(&mut state);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+        // A key that is refused still ends the count
+        state.read_only = true;
+        press(&mut state, KeyCode::Char('3'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Right, KeyModifiers::ALT);
+        press(&mut state, KeyCode::Char('k'), KeyModifiers::NONE);
+        assert_eq!(task_selection(&mut state), Some(2));
+    }
+
+    #[test]
+    fn jumps_to_first_and_last_task() {
+        let mut state = with_tasks(4);
+        press(&mut state, KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(task_selection(&mut state), Some(3));
+        press(&mut state, KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(task_selection(&mut state), Some(0));
+        let mut state = with_tasks(0);
+        press(&mut state, KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(task_selection(&mut state), None);
+        press(&mut state, KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(task_selection(&mut state), None);
+    }
+
+    #[test]
+    fn jumps_to_first_and_last_subproject() {
+        let mut state = with_tasks(0);
+        let project = state.journal.project().unwrap();
+        project.subprojects.push_item(SubProject::new("Second"));
+        project.subprojects.push_item(SubProject::new("Third"));
+        press(&mut state, KeyCode::End, KeyModifiers::NONE);
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.subprojects.selection(), Some(2));
+        press(&mut state, KeyCode::Home, KeyModifiers::NONE);
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.subprojects.selection(), Some(0));
+    }
+
+    /// Submit text to the open project prompt
+    fn answer_prompt(state: &mut App, text: &str) {
+        state.journal.project().unwrap().prompt.set_text(text);
+        press(state, KeyCode::Enter, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn sets_password_only_when_confirmed() {
+        let mut state = App::new(PathBuf::new());
+        press(&mut state, KeyCode::Char('p'), KeyModifiers::CONTROL);
+        answer_prompt(&mut state, "secret");
+        answer_prompt(&mut state, "secert");
+        assert!(state.journal.password.is_empty());
+        // A mismatch starts over
+        assert!(matches!(
+            state.journal.project().unwrap().prompt_request,
+            Some(JournalPrompt::SetPassword)
+        ));
+        answer_prompt(&mut state, "secret");
+        answer_prompt(&mut state, "secret");
+        assert_eq!(&*state.journal.password, "secret");
+        assert!(state.journal.project().unwrap().prompt_request.is_none());
+    }
+
+    #[test]
+    fn paste_goes_to_the_open_prompt() {
+        let mut state = with_tasks(1);
+        handle_paste("ignored", &mut state);
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.prompt.get_text(), "");
+        set_project_prompt(
+            project,
+            JournalPrompt::RenameTask,
+            "Rename:",
+            "Task ",
+            false,
+        );
+        handle_paste("one\ntwo", &mut state);
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        let project = state.journal.project().unwrap();
+        let task = project.subproject().unwrap().task().unwrap();
+        assert_eq!(task.desc, "Task onetwo");
+    }
+
+    fn subproject_names(project: &Project) -> Vec<String> {
+        project.subprojects.iter().map(|s| s.name.clone()).collect()
+    }
+
+    fn project_names(journal: &Journal) -> Vec<String> {
+        journal.projects.iter().map(|p| p.name.clone()).collect()
+    }
+
+    #[test]
+    fn moves_subproject_across_projects() {
+        let mut state = App::new(PathBuf::new());
+        let mut first = Project::new("First");
+        first.subprojects = vec![SubProject::new("Stays"), SubProject::new("Moves")].into();
+        first.subprojects.select(1).unwrap();
+        let mut second = Project::new("Second");
+        second.subprojects = vec![SubProject::new("Other")].into();
+        second.subprojects.select(0).unwrap();
+        state.journal.projects = vec![first, second].into();
+        state.journal.projects.select(0).unwrap();
+        press(&mut state, KeyCode::PageDown, KeyModifiers::SHIFT);
+        assert_eq!(state.journal.projects.selection(), Some(1));
+        let projects = &state.journal.projects;
+        assert_eq!(
+            subproject_names(projects.get_item(Some(0)).unwrap()),
+            ["Stays"]
+        );
+        let second = projects.get_item(Some(1)).unwrap();
+        assert_eq!(subproject_names(second), ["Moves", "Other"]);
+        assert_eq!(second.subprojects.selected().unwrap().name, "Moves");
+        press(&mut state, KeyCode::PageUp, KeyModifiers::SHIFT);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+        let projects = &state.journal.projects;
+        let first = projects.get_item(Some(0)).unwrap();
+        assert!(subproject_names(first).contains(&"Moves".to_owned()));
+        assert_eq!(first.subprojects.selected().unwrap().name, "Moves");
+        assert_eq!(
+            subproject_names(projects.get_item(Some(1)).unwrap()),
+            ["Other"]
+        );
+    }
+
+    #[test]
+    fn moves_project_to_position() {
+        let mut state = App::new(PathBuf::new());
+        state.journal.projects = ["A", "B", "C", "D"].map(Project::new).to_vec().into();
+        state.journal.projects.select(1).unwrap();
+        press(&mut state, KeyCode::End, KeyModifiers::ALT);
+        assert_eq!(project_names(&state.journal), ["A", "C", "D", "B"]);
+        assert_eq!(state.journal.projects.selection(), Some(3));
+        press(&mut state, KeyCode::Home, KeyModifiers::ALT);
+        assert_eq!(project_names(&state.journal), ["B", "A", "C", "D"]);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+        // The count prefix is a position, not a project to select
+        press(&mut state, KeyCode::Char('3'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Home, KeyModifiers::ALT);
+        assert_eq!(project_names(&state.journal), ["A", "C", "B", "D"]);
+        assert_eq!(state.journal.projects.selection(), Some(2));
+    }
+
+    /// Text of each row of the screen, drawn at the given size
+    fn screen(state: &App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| crate::ui::draw(frame, state))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn renames_task_inline() {
+        let mut state = with_tasks(2);
+        press(&mut state, KeyCode::Char('r'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::End, KeyModifiers::NONE);
+        press(&mut state, KeyCode::Char('!'), KeyModifiers::NONE);
+        // Edited in the row of the task, without a centered prompt
+        let rows = screen(&state, 80, 24);
+        assert!(rows.iter().any(|row| row.contains("Task 0!")));
+        assert!(!rows.iter().any(|row| row.contains("Rename Task:")));
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        let project = state.journal.project().unwrap();
+        let subproject = project.subproject().unwrap();
+        assert_eq!(subproject.task().unwrap().desc, "Task 0!");
+        // The centered prompt remains as a fallback
+        state.settings.inline_rename = false;
+        press(&mut state, KeyCode::Char('r'), KeyModifiers::NONE);
+        let rows = screen(&state, 80, 24);
+        assert!(rows.iter().any(|row| row.contains("Rename Task:")));
+    }
+
+    #[test]
+    fn bulk_adds_one_task_per_line() {
+        let mut state = with_tasks(1);
+        press(&mut state, KeyCode::Char('A'), KeyModifiers::SHIFT);
+        let project = state.journal.project().unwrap();
+        project.prompt.set_text("  first \n\n second\n   \nthird");
+        press(&mut state, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let project = state.journal.project().unwrap();
+        let tasks = &project.subproject().unwrap().tasks;
+        let descs: Vec<&str> = tasks.iter().map(|t| t.desc.as_str()).collect();
+        assert_eq!(descs, ["Task 0", "first", "second", "third"]);
+    }
+
+    #[test]
+    fn imports_project_under_a_unique_name() {
+        let dir = scratch_dir("import_project");
+        let mut project = Project::new("New Project");
+        project.subprojects = vec![SubProject::new("Imported")].into();
+        project
+            .subprojects
+            .get_item_mut(Some(0))
+            .unwrap()
+            .tasks
+            .push_item(Task::new("Carried over"));
+        project
+            .save_encrypt(&dir.join("project"), "", Algorithm::default())
+            .unwrap();
+        let mut state = App::new(dir);
+        import_project(&mut state, "project", "").unwrap();
+        import_project(&mut state, "project", "").unwrap();
+        assert_eq!(
+            project_names(&state.journal),
+            ["New Project", "New Project (2)", "New Project (3)"]
+        );
+        let imported = state.journal.project().unwrap();
+        assert_eq!(subproject_names(imported), ["Imported"]);
+        let tasks = &imported.subprojects.get_item(Some(0)).unwrap().tasks;
+        assert_eq!(tasks.as_strings(), ["Carried over"]);
+    }
+
+    #[test]
+    fn duplicates_journal_without_switching_to_it() {
+        let dir = scratch_dir("duplicate_journal");
+        let mut state = with_tasks(2);
+        state.filepath = dir.join("original");
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        subproject
+            .tasks
+            .get_item_mut(Some(0))
+            .unwrap()
+            .toggle_completed();
+        save_state(&mut state, None).unwrap();
+        duplicate_journal(&mut state, &dir.join("copy"), true).unwrap();
+        assert_eq!(state.filepath, dir.join("original"));
+        let copy = Journal::load_decrypt(&dir.join("copy"), "").unwrap();
+        let subprojects = &copy.projects.get_item(Some(0)).unwrap().subprojects;
+        let tasks = &subprojects.get_item(Some(0)).unwrap().tasks;
+        assert_eq!(tasks.as_strings(), ["Task 1"]);
+        // The open journal is unchanged, and an existing file is not overwritten
+        assert_eq!(task_count(&mut state), 2);
+        assert!(duplicate_journal(&mut state, &dir.join("copy"), false).is_err());
+    }
+
+    #[test]
+    fn new_journal_from_template_resets_completion() {
+        let dir = scratch_dir("new_from_template");
+        let mut state = with_tasks(2);
+        state.datadir = dir.clone();
+        state.journal.name = "Weekly".to_owned();
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        subproject
+            .tasks
+            .get_item_mut(Some(1))
+            .unwrap()
+            .toggle_completed();
+        save_template(&mut state, "weekly").unwrap();
+        let mut state = App::new(dir.clone());
+        new_from_template(&mut state, "weekly", "week 2").unwrap();
+        assert_eq!(state.journal.name, "week 2");
+        assert_eq!(state.filepath, dir.join("week 2"));
+        let saved = Journal::load_decrypt(&dir.join("week 2"), "").unwrap();
+        let project = saved.projects.get_item(Some(0)).unwrap();
+        let tasks = &project.subprojects.get_item(Some(0)).unwrap().tasks;
+        assert_eq!(tasks.as_strings(), ["Task 0", "Task 1"]);
+        assert!(tasks.iter().all(|task| !task.is_completed()));
+        assert!(new_from_template(&mut state, "weekly", "week 2").is_err());
+    }
+
+    fn selected_task<'a>(state: &'a mut App) -> &'a Task {
+        state.journal.selected_task().unwrap()
+    }
+
+    #[test]
+    fn picks_task_color() {
+        let mut state = with_tasks(1);
+        press(&mut state, KeyCode::Char('c'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut state, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        let color = selected_task(&mut state).color.clone().unwrap();
+        assert_eq!(color, "yellow");
+        assert_eq!(
+            crate::ui::styles::label_color(&color),
+            Some(tui::style::Color::Yellow)
         );
+        // Every choice maps to a color, and the first choice removes it
+        assert!(TASK_COLORS
+            .iter()
+            .all(|c| crate::ui::styles::label_color(c).is_some()));
+        press(&mut state, KeyCode::Char('c'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Char('1'), KeyModifiers::NONE);
+        assert!(selected_task(&mut state).color.is_none());
+    }
+
+    #[test]
+    fn escape_steps_up_the_hierarchy() {
+        let mut state = with_tasks(2);
+        state.journal.project().unwrap().filter = Some(TaskFilter::new("Task").unwrap());
+        press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(state.journal.project().unwrap().filter.is_none());
+        assert_eq!(task_selection(&mut state), Some(0));
+        press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(task_selection(&mut state), None);
+        assert!(state.focus == FocusRegion::Subprojects);
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.subprojects.selection(), Some(0));
+        press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.subprojects.selection(), None);
+        // Nothing left to step out of
+        press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+    }
+
+    #[test]
+    fn focus_cycles_through_regions() {
+        let mut state = with_tasks(2);
+        let mut order = Vec::new();
+        for _ in 0..4 {
+            press(&mut state, KeyCode::Char('f'), KeyModifiers::NONE);
+            order.push(state.focus);
+        }
+        assert!(
+            order
+                == [
+                    FocusRegion::Tabs,
+                    FocusRegion::Subprojects,
+                    FocusRegion::Tasks,
+                    FocusRegion::Tabs
+                ]
+        );
+        // Horizontal keys move between projects in the tab region, other keys still apply
+        state
+            .journal
+            .projects
+            .add_item(Project::new("Second"), false);
+        press(&mut state, KeyCode::Char('l'), KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selection(), Some(1));
+        press(&mut state, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert!(state.focus == FocusRegion::Tasks);
+    }
+
+    #[test]
+    fn projects_keep_their_view_across_tab_switches() {
+        let mut state = with_tasks(3);
+        state
+            .journal
+            .projects
+            .add_item(Project::new("Second"), false);
+        let project = state.journal.project().unwrap();
+        project.subprojects.push_item(SubProject::new("Other"));
+        project.subproject().unwrap().tasks.select(2).unwrap();
+        project.split_vertical = true;
+        project.focused_width_percent = 70;
+        project.filter = Some(TaskFilter::new("task").unwrap());
+        press(&mut state, KeyCode::PageDown, KeyModifiers::CONTROL);
+        assert_eq!(state.journal.projects.selection(), Some(1));
+        press(&mut state, KeyCode::PageUp, KeyModifiers::CONTROL);
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.subprojects.selection(), Some(0));
+        assert_eq!(project.subproject().unwrap().tasks.selection(), Some(2));
+        assert!(project.split_vertical);
+        assert_eq!(project.focused_width_percent, 70);
+        assert_eq!(project.filter.as_ref().unwrap().pattern(), "task");
+    }
+
+    #[test]
+    fn mirror_is_identical_for_identical_content() {
+        let dir = scratch_dir("mirror_deterministic");
+        let mut state = with_tasks(3);
+        state.settings.plaintext_mirror = true;
+        state.filepath = dir.join("journal");
+        save_state(&mut state, None).unwrap();
+        let mirror = mirror_path(&state.filepath);
+        let first = fs::read(&mirror).unwrap();
+        // Selections are not content
+        press(&mut state, KeyCode::Char('j'), KeyModifiers::NONE);
+        save_state(&mut state, None).unwrap();
+        assert_eq!(fs::read(&mirror).unwrap(), first);
+        assert!(String::from_utf8(first).unwrap().contains("- [ ] Task 2"));
+        // Nor written at all unless enabled
+        let mut state = with_tasks(1);
+        state.filepath = dir.join("unmirrored");
+        save_state(&mut state, None).unwrap();
+        assert!(!mirror_path(&state.filepath).exists());
+    }
+
+    #[test]
+    fn read_only_ignores_mutating_keys() {
+        let mut state = with_tasks(2);
+        state.read_only = true;
+        let fingerprint = state.journal.fingerprint();
+        // Layout is saved with the project but left out of the fingerprint
+        let layout = |state: &mut App| {
+            let project = state.journal.project().unwrap();
+            (
+                project.focused_width_percent,
+                project.split_vertical,
+                project.show_ages,
+                project.hide_empty,
+            )
+        };
+        let original_layout = layout(&mut state);
+        for (code, modifiers) in [
+            (KeyCode::Char('n'), KeyModifiers::NONE),
+            (KeyCode::Char('d'), KeyModifiers::NONE),
+            (KeyCode::Char(' '), KeyModifiers::NONE),
+            (KeyCode::Tab, KeyModifiers::NONE),
+            (KeyCode::Down, KeyModifiers::CONTROL),
+            (KeyCode::Char('='), KeyModifiers::NONE),
+            (KeyCode::Char('-'), KeyModifiers::NONE),
+            (KeyCode::Char('\\'), KeyModifiers::NONE),
+            (KeyCode::Char('z'), KeyModifiers::NONE),
+            (KeyCode::Char('a'), KeyModifiers::NONE),
+            (KeyCode::Char('v'), KeyModifiers::NONE),
+            (KeyCode::Char('c'), KeyModifiers::ALT),
+            (KeyCode::Char('C'), KeyModifiers::ALT | KeyModifiers::SHIFT),
+            (KeyCode::Char('D'), KeyModifiers::SHIFT),
+        ] {
+            press(&mut state, code, modifiers);
+            assert!(state.journal.project().unwrap().prompt_request.is_none());
+            assert!(state.confirm_request.is_none());
+            assert!(state.file_request.is_none());
+        }
+        assert_eq!(state.journal.fingerprint(), fingerprint);
+        assert_eq!(layout(&mut state), original_layout);
+        assert!(!selected_task(&mut state).is_completed());
+        assert_eq!(state.feedback().unwrap().message, "Journal is read-only");
+        // Navigation still works
+        press(&mut state, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(task_selection(&mut state), Some(1));
+    }
+
+    #[test]
+    fn focus_width_follows_configured_step_and_bounds() {
+        let mut state = with_tasks(0);
+        state.settings.focus_width_step = 12;
+        state.settings.focus_width_max = 80;
+        let project = state.journal.project().unwrap();
+        project.subprojects.push_item(SubProject::new("Second"));
+        project.subprojects.push_item(SubProject::new("Third"));
+        let width = |state: &mut App| state.journal.project().unwrap().focused_width_percent;
+        assert_eq!(width(&mut state), 40);
+        press(&mut state, KeyCode::Char('='), KeyModifiers::NONE);
+        assert_eq!(width(&mut state), 52);
+        for _ in 0..3 {
+            press(&mut state, KeyCode::Char('='), KeyModifiers::NONE);
+        }
+        assert_eq!(width(&mut state), 80);
+        // Never narrower than an even share of the subprojects
+        for _ in 0..10 {
+            press(&mut state, KeyCode::Char('-'), KeyModifiers::NONE);
+        }
+        assert_eq!(width(&mut state), 33);
+    }
+
+    #[test]
+    fn explains_keys_without_a_target() {
+        let mut state = with_tasks(0);
+        press(&mut state, KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(state.feedback().unwrap().message, "No task selected");
+        assert!(state.journal.project().unwrap().prompt_request.is_none());
+        state.journal.project().unwrap().subprojects.deselect();
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+        assert_eq!(state.feedback().unwrap().message, "No subproject selected");
+        state.journal.projects.deselect();
+        press(&mut state, KeyCode::Char('r'), KeyModifiers::ALT);
+        assert_eq!(state.feedback().unwrap().message, "No project selected");
+    }
+
+    #[test]
+    fn quitting_with_unsaved_changes_asks_first() {
+        let mut state = App::new(PathBuf::new());
+        press(&mut state, KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert!(state.quit);
+        let mut state = with_tasks(1);
+        assert!(state.is_dirty());
+        press(&mut state, KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert!(!state.quit);
+        assert!(matches!(state.confirm_request, Some(ConfirmRequest::Quit)));
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+        assert!(!state.quit && state.confirm_request.is_none());
+        press(&mut state, KeyCode::Char('q'), KeyModifiers::CONTROL);
+        press(&mut state, KeyCode::Char('y'), KeyModifiers::NONE);
+        assert!(state.quit);
+        let mut state = with_tasks(1);
+        state.settings.confirm_quit = false;
+        press(&mut state, KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert!(state.quit);
+    }
+
+    #[test]
+    fn moves_marked_tasks_in_order() {
+        let mut state = with_tasks(5);
+        let project = state.journal.project().unwrap();
+        project.subprojects.push_item(SubProject::new("Second"));
+        let mut third = SubProject::new("Third");
+        third.tasks.push_item(Task::new("Existing"));
+        project.subprojects.push_item(third);
+        for index in [4, 1, 3] {
+            let project = state.journal.project().unwrap();
+            project.subproject().unwrap().tasks.select(index).unwrap();
+            press(&mut state, KeyCode::Char('m'), KeyModifiers::NONE);
+        }
+        press(&mut state, KeyCode::Char('3'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Right, KeyModifiers::ALT);
+        assert_eq!(state.journal.projects.selection(), Some(0));
+        let project = state.journal.project().unwrap();
+        assert_eq!(project.subprojects.selection(), Some(2));
+        let subprojects = &project.subprojects;
+        let descs = |index| -> Vec<String> {
+            let tasks = &subprojects.get_item(Some(index)).unwrap().tasks;
+            tasks.iter().map(|task| task.desc.clone()).collect()
+        };
+        assert_eq!(descs(0), ["Task 0", "Task 2"]);
+        assert_eq!(descs(2), ["Existing", "Task 1", "Task 3", "Task 4"]);
+        assert!(subprojects
+            .iter()
+            .flat_map(|subproject| subproject.tasks.iter())
+            .all(|task| !task.marked));
+        assert_eq!(state.feedback().unwrap().message, "Moved 3 tasks");
+    }
+
+    #[test]
+    fn restores_checkpoint_without_touching_the_file() {
+        let dir = scratch_dir("checkpoints");
+        let mut state = with_tasks(1);
+        state.datadir = dir.clone();
+        state.filepath = dir.join("journal");
+        state.journal.password = Password::from("secret");
+        save_state(&mut state, None).unwrap();
+        save_checkpoint(&mut state, "one task").unwrap();
+        assert!(save_checkpoint(&mut state, "../escape").is_err());
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        subproject.tasks.push_item(Task::new("Later"));
+        save_state(&mut state, None).unwrap();
+        restore_checkpoint(&mut state, "one task").unwrap();
+        assert_eq!(task_count(&mut state), 1);
+        // Checkpoints are encrypted with the journal password
+        let checkpoint = checkpoint_path(&state, "one task");
+        assert!(Journal::load_decrypt(&checkpoint, "").is_err());
+        let saved = Journal::load_decrypt(&state.filepath, "secret").unwrap();
+        let project = saved.projects.get_item(Some(0)).unwrap();
+        assert_eq!(
+            project.subprojects.get_item(Some(0)).unwrap().tasks.len(),
+            2
+        );
+    }
+
+    #[test]
+    fn invalid_filter_regex_is_reported() {
+        let mut state = with_tasks(1);
+        let project = state.journal.project().unwrap();
+        set_project_prompt(project, JournalPrompt::Filter, "Filter:", "re:(", false);
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(state.journal.project().unwrap().filter.is_none());
+        let feedback = state.feedback().unwrap();
+        assert!(feedback.message.starts_with("Invalid regex"));
+    }
+
+    #[test]
+    fn cycles_through_matches_across_subprojects() {
+        let mut state = App::new(PathBuf::new());
+        let project = state.journal.project().unwrap();
+        project.subprojects = vec![SubProject::new("A"), SubProject::new("B")].into();
+        for (index, descs) in [(0, ["match one", "other"]), (1, ["other", "match two"])] {
+            let subproject = project.subprojects.get_item_mut(Some(index)).unwrap();
+            for desc in descs {
+                subproject.tasks.push_item(Task::new(desc));
+            }
+        }
+        project.subprojects.select(0).unwrap();
+        project.subproject().unwrap().tasks.select(0).unwrap();
+        project.filter = Some(TaskFilter::new("match").unwrap());
+        let position = |state: &mut App| {
+            let project = state.journal.project().unwrap();
+            (project.subprojects.selection(), task_selection(state))
+        };
+        let mut visited = Vec::new();
+        for (code, modifiers) in [
+            (KeyCode::Char('n'), KeyModifiers::NONE),
+            (KeyCode::Char('n'), KeyModifiers::NONE),
+            (KeyCode::Char('N'), KeyModifiers::SHIFT),
+            (KeyCode::Char('N'), KeyModifiers::SHIFT),
+        ] {
+            press(&mut state, code, modifiers);
+            visited.push(position(&mut state));
+        }
+        assert_eq!(
+            visited,
+            [
+                (Some(1), Some(1)),
+                (Some(0), Some(0)),
+                (Some(1), Some(1)),
+                (Some(0), Some(0))
+            ]
+        );
+        state.journal.project().unwrap().filter = Some(TaskFilter::new("nothing").unwrap());
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+        assert_eq!(state.feedback().unwrap().message, "No matching tasks");
+    }
+
+    #[test]
+    fn added_tasks_keep_the_sort_order() {
+        for key in TaskSortKey::ALL {
+            let mut state = with_tasks(0);
+            let subproject = state.journal.project().unwrap().subproject().unwrap();
+            for (created_at, due_at, priority, completed_at) in [
+                ("2020-01-01", Some("2023-01-05"), 1, Some("2020-02-01")),
+                ("2021-01-01", None, 3, None),
+                ("2022-01-01", Some("2023-01-01"), 0, None),
+            ] {
+                subproject.tasks.push_item(Task {
+                    created_at: format!("{created_at} 00:00:00"),
+                    due_at: due_at.map(str::to_owned),
+                    priority,
+                    completed_at: completed_at.map(|c| format!("{c} 00:00:00")),
+                    ..Task::new(created_at)
+                });
+            }
+            subproject.sort_key = Some(key);
+            subproject.sort_tasks();
+            subproject.tasks.select(0).unwrap();
+            press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+            answer_prompt(&mut state, "New");
+            let subproject = state.journal.project().unwrap().subproject().unwrap();
+            let tasks: Vec<&Task> = subproject.tasks.iter().collect();
+            assert_eq!(tasks.len(), 4);
+            assert!(tasks
+                .windows(2)
+                .all(|pair| key.compare(pair[0], pair[1]).is_le()));
+            assert_eq!(subproject.task().unwrap().desc, "New");
+        }
+        // Manual reordering turns sorting off
+        let mut state = with_tasks(2);
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        subproject.sort_key = Some(TaskSortKey::Created);
+        press(&mut state, KeyCode::Down, KeyModifiers::CONTROL);
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        assert!(subproject.sort_key.is_none());
+    }
+
+    #[test]
+    fn autosaves_only_encrypted_journals_unless_allowed() {
+        let dir = scratch_dir("autosave_encrypted");
+        let mut state = App::new(dir.clone());
+        state.filepath = dir.join("journal");
+        save_state(&mut state, None).unwrap();
+        state
+            .journal
+            .projects
+            .add_item(Project::new("Unsaved"), false);
+        autosave(&mut state);
+        assert!(state.is_dirty());
+        // Asked once for a password
+        let project = state.journal.project().unwrap();
+        assert!(matches!(
+            project.prompt_request,
+            Some(JournalPrompt::SetPassword)
+        ));
+        project.prompt_request = None;
+        autosave(&mut state);
+        assert!(state.journal.project().unwrap().prompt_request.is_none());
+        state.journal.password = Password::from("secret");
+        autosave(&mut state);
+        assert!(!state.is_dirty());
+        let saved = Journal::load_decrypt(&state.filepath, "secret").unwrap();
+        assert_eq!(project_names(&saved), ["New Project", "Unsaved"]);
+        // Or when unencrypted autosave is allowed
+        state.journal.password = Password::default();
+        state.journal.name = "Renamed".to_owned();
+        state.settings.autosave_unencrypted = true;
+        autosave(&mut state);
+        assert!(!state.is_dirty());
+    }
+
+    #[test]
+    fn goes_to_project_by_name() {
+        let mut state = App::new(PathBuf::new());
+        for name in ["Alpha", "Beta", "Gamma"] {
+            state.journal.projects.push_item(Project::new(name));
+        }
+        press(&mut state, KeyCode::Char('g'), KeyModifiers::CONTROL);
+        state.prompt.set_text("gam");
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selected().unwrap().name, "Gamma");
+        press(&mut state, KeyCode::Char('g'), KeyModifiers::CONTROL);
+        state.prompt.set_text("delta");
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(state.journal.projects.selected().unwrap().name, "Gamma");
+        assert!(state
+            .feedback()
+            .unwrap()
+            .message
+            .contains("No project matching"));
+    }
+
+    #[test]
+    fn f12_toggles_debug_view_and_swallows_other_keys() {
+        let mut state = with_tasks(2);
+        press(&mut state, KeyCode::F(12), KeyModifiers::NONE);
+        assert!(state.debug);
+        press(&mut state, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(task_selection(&mut state), Some(0));
+        press(&mut state, KeyCode::F(12), KeyModifiers::NONE);
+        assert!(!state.debug);
+        press(&mut state, KeyCode::F(12), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(!state.debug);
+    }
+
+    #[test]
+    fn stores_normalized_task_names() {
+        let mut state = with_tasks(0);
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+        answer_prompt(&mut state, "  Pasted\ttask\u{7} ");
+        assert_eq!(selected_task(&mut state).desc, "Pasted task");
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+        answer_prompt(&mut state, " \u{7} ");
+        assert_eq!(task_count(&mut state), 1);
+        assert_eq!(state.feedback().unwrap().message, "Name cannot be empty");
+        // The prompt stays open for another try
+        answer_prompt(&mut state, "Second");
+        assert_eq!(task_count(&mut state), 2);
+    }
+
+    #[test]
+    fn empty_add_submissions_leave_lists_unchanged() {
+        let mut state = with_tasks(1);
+        let projects = project_names(&state.journal);
+        let subprojects = subproject_names(state.journal.project().unwrap());
+        for (code, modifiers) in [
+            (KeyCode::Char('n'), KeyModifiers::ALT),
+            (KeyCode::Char('N'), KeyModifiers::SHIFT),
+            (KeyCode::Char('n'), KeyModifiers::NONE),
+        ] {
+            press(&mut state, code, modifiers);
+            answer_prompt(&mut state, "");
+            answer_prompt(&mut state, "   ");
+            assert!(state.journal.project().unwrap().prompt_request.is_some());
+            press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        }
+        assert_eq!(project_names(&state.journal), projects);
+        assert_eq!(
+            subproject_names(state.journal.project().unwrap()),
+            subprojects
+        );
+        assert_eq!(task_count(&mut state), 1);
+    }
+
+    #[test]
+    fn keeps_projects_alphabetical() {
+        let mut state = App::new(PathBuf::new());
+        state.journal.projects = ["Work", "alpha"].map(Project::new).to_vec().into();
+        state.journal.projects.select(0).unwrap();
+        press(&mut state, KeyCode::Char('a'), KeyModifiers::ALT);
+        assert_eq!(project_names(&state.journal), ["alpha", "Work"]);
+        assert_eq!(state.journal.projects.selected().unwrap().name, "Work");
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::ALT);
+        answer_prompt(&mut state, "Beta");
+        assert_eq!(project_names(&state.journal), ["alpha", "Beta", "Work"]);
+        assert_eq!(state.journal.projects.selected().unwrap().name, "Beta");
+        press(&mut state, KeyCode::Char('r'), KeyModifiers::ALT);
+        answer_prompt(&mut state, "Zeta");
+        assert_eq!(project_names(&state.journal), ["alpha", "Work", "Zeta"]);
+        assert_eq!(state.journal.projects.selected().unwrap().name, "Zeta");
+        // Manual reordering is disabled
+        press(&mut state, KeyCode::PageUp, KeyModifiers::ALT);
+        assert_eq!(project_names(&state.journal), ["alpha", "Work", "Zeta"]);
+    }
+
+    #[test]
+    fn completes_and_reopens_all_tasks() {
+        let mut state = with_tasks(3);
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        subproject.tasks.get_item_mut(Some(1)).unwrap().completed_at = Some(timestamp_now());
+        let completed = |state: &mut App| {
+            let subproject = state.journal.project().unwrap().subproject().unwrap();
+            subproject.tasks.iter().filter(|t| t.is_completed()).count()
+        };
+        press(&mut state, KeyCode::Char('X'), KeyModifiers::SHIFT);
+        assert_eq!(completed(&mut state), 3);
+        assert_eq!(state.feedback().unwrap().message, "Completed 2 tasks");
+        press(&mut state, KeyCode::Char('U'), KeyModifiers::SHIFT);
+        assert_eq!(completed(&mut state), 0);
+        assert_eq!(state.feedback().unwrap().message, "Reopened 3 tasks");
+    }
+
+    #[test]
+    fn confirms_clearing_many_completed_tasks() {
+        let mut state = with_tasks(CLEAR_CONFIRM_THRESHOLD + 2);
+        let subproject = state.journal.project().unwrap().subproject().unwrap();
+        for task in subproject.tasks.iter_mut().skip(1) {
+            task.completed_at = Some(timestamp_now());
+        }
+        press(&mut state, KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert!(matches!(
+            state.confirm_request,
+            Some(ConfirmRequest::ClearCompleted)
+        ));
+        assert_eq!(task_count(&mut state), CLEAR_CONFIRM_THRESHOLD + 2);
+        press(&mut state, KeyCode::Char('y'), KeyModifiers::NONE);
+        assert_eq!(task_count(&mut state), 1);
+        assert_eq!(task_selection(&mut state), Some(0));
+        assert_eq!(
+            state.feedback().unwrap().message,
+            format!("Cleared {} completed tasks", CLEAR_CONFIRM_THRESHOLD + 1)
+        );
+    }
+
+    #[test]
+    fn copying_marked_tasks_needs_a_mark() {
+        let mut state = with_tasks(2);
+        press(&mut state, KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(state.feedback().unwrap().message, "No tasks marked");
+    }
+
+    /// A saved journal in a fresh data folder, and a change to it saved by someone else
+    fn changed_on_disk(name: &str) -> App<'static> {
+        let dir = scratch_dir(name);
+        let mut state = App::new(dir.clone());
+        state.filepath = dir.join("journal");
+        save_state(&mut state, None).unwrap();
+        let mut external = Journal::load_decrypt(&state.filepath, "").unwrap();
+        external.projects.add_item(Project::new("External"), false);
+        external
+            .save_encrypt(&state.filepath, "", Algorithm::default())
+            .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&state.filepath)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        state
+            .journal
+            .projects
+            .add_item(Project::new("Local"), false);
+        state
+    }
+
+    #[test]
+    fn quitting_while_locked_saves_first() {
+        let dir = scratch_dir("quit_locked");
+        let mut state = App::new(dir.clone());
+        state.filepath = dir.join("journal");
+        state.journal.password = Password::from("secret");
+        save_state(&mut state, None).unwrap();
+        state
+            .journal
+            .projects
+            .add_item(Project::new("Unsaved"), false);
+        state.locked = true;
+        press(&mut state, KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert!(state.quit);
+        let saved = Journal::load_decrypt(&state.filepath, "secret").unwrap();
+        assert_eq!(project_names(&saved), ["New Project", "Unsaved"]);
+    }
+
+    #[test]
+    fn quitting_while_locked_keeps_changes_that_cannot_be_saved() {
+        let mut state = changed_on_disk("quit_locked_changed");
+        state.locked = true;
+        press(&mut state, KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert!(!state.quit);
+        let saved = Journal::load_decrypt(&state.filepath, "").unwrap();
+        assert_eq!(project_names(&saved), ["New Project", "External"]);
+    }
+
+    #[test]
+    fn backs_off_after_wrong_passwords_only() {
+        let dir = scratch_dir("password_backoff");
+        Project::new("Secret")
+            .save_encrypt(&dir.join("project"), "secret", Algorithm::default())
+            .unwrap();
+        fs::write(
+            dir.join("corrupt"),
+            encrypt(b"\xff", "", Algorithm::default(), FORMAT_VERSION).unwrap(),
+        )
+        .unwrap();
+        let mut state = App::new(dir);
+        let submit = |state: &mut App, request: AppPrompt, password: &str| {
+            set_app_prompt(state, request, "Password:", password, true);
+            press(state, KeyCode::Enter, KeyModifiers::NONE);
+        };
+        submit(&mut state, AppPrompt::LoadFile("corrupt".to_owned()), "");
+        assert_eq!(state.password_failures, 0);
+        submit(
+            &mut state,
+            AppPrompt::ImportProject("project".to_owned()),
+            "guess",
+        );
+        assert_eq!(state.password_failures, 1);
+        // Refused while waiting, with the prompt kept open
+        submit(
+            &mut state,
+            AppPrompt::ImportProject("project".to_owned()),
+            "secret",
+        );
+        assert!(state.prompt_request.is_some());
+        assert_eq!(state.journal.projects.len(), 1);
+        state.password_retry_at = None;
+        state.prompt_request = None;
+        submit(
+            &mut state,
+            AppPrompt::ImportProject("project".to_owned()),
+            "secret",
+        );
+        assert_eq!(state.journal.projects.len(), 2);
+        assert_eq!(state.password_failures, 0);
+    }
+
+    #[test]
+    fn backs_off_when_unlocking_projects() {
+        let mut state = App::new(PathBuf::new());
+        let mut project = Project::new("Secret");
+        project.password = Password::from("secret");
+        let project = project.sealed(Algorithm::default()).unwrap();
+        state.journal.projects.add_item(project, true);
+        let submit = |state: &mut App, password: &str| {
+            let project = state.journal.project().unwrap();
+            set_project_prompt(
+                project,
+                JournalPrompt::UnlockProject,
+                "Password:",
+                password,
+                true,
+            );
+            press(state, KeyCode::Enter, KeyModifiers::NONE);
+        };
+        submit(&mut state, "guess");
+        assert_eq!(state.password_failures, 1);
+        submit(&mut state, "secret");
+        assert!(state.journal.project().unwrap().is_locked());
+        state.password_retry_at = None;
+        state.journal.project().unwrap().prompt_request = None;
+        submit(&mut state, "secret");
+        assert!(!state.journal.project().unwrap().is_locked());
+        assert_eq!(state.password_failures, 0);
+    }
+
+    #[test]
+    fn rename_moves_mirror_and_checkpoints() {
+        let dir = scratch_dir("rename_file");
+        let mut state = App::new(dir.clone());
+        state.settings.plaintext_mirror = true;
+        state.filepath = dir.join("old");
+        save_state(&mut state, None).unwrap();
+        save_checkpoint(&mut state, "before").unwrap();
+        rename_file(&mut state, "new").unwrap();
+        assert_eq!(state.filepath, dir.join("new"));
+        assert!(!dir.join("old").exists() && dir.join("new").exists());
+        assert!(!dir.join("old.mirror.md").exists() && dir.join("new.mirror.md").exists());
+        let checkpoints = dir.join(CHECKPOINTS_DIR);
+        assert!(!checkpoints.join("old").exists());
+        assert!(checkpoints.join("new").join("before").exists());
+    }
+
+    #[test]
+    fn save_reloads_journal_changed_on_disk() {
+        let mut state = changed_on_disk("reload_changed");
+        press(&mut state, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(matches!(
+            state.choice_request,
+            Some(ChoiceRequest::ChangedOnDisk)
+        ));
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(project_names(&state.journal), ["New Project", "External"]);
+        assert_eq!(state.file_modified, file_modified(&state.filepath));
+        assert_eq!(state.saved_fingerprint, state.journal.fingerprint());
+    }
+
+    #[test]
+    fn save_overwrites_journal_changed_on_disk() {
+        let mut state = changed_on_disk("overwrite_changed");
+        press(&mut state, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        press(&mut state, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut state, KeyCode::Enter, KeyModifiers::NONE);
+        let saved = Journal::load_decrypt(&state.filepath, "").unwrap();
+        assert_eq!(project_names(&saved), ["New Project", "Local"]);
+        assert_eq!(state.file_modified, file_modified(&state.filepath));
+        // Saving again goes ahead without asking
+        press(&mut state, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(state.choice_request.is_none());
+    }
+
+    #[test]
+    fn load_prompts_for_password_only_when_decryption_fails() {
+        let dir = scratch_dir("try_load_file");
+        Journal::new("Locked")
+            .save_encrypt(&dir.join("locked"), "secret", Algorithm::default())
+            .unwrap();
+        let garbage = encrypt(b"\xff\xff", "", Algorithm::default(), FORMAT_VERSION).unwrap();
+        fs::write(dir.join("corrupt"), garbage).unwrap();
+        let mut state = App::new(dir.clone());
+        try_load_file(&mut state, "locked");
+        assert!(matches!(state.prompt_request, Some(AppPrompt::LoadFile(_))));
+        let mut state = App::new(dir);
+        try_load_file(&mut state, "corrupt");
+        assert!(state.prompt_request.is_none());
+        let feedback = state.feedback().unwrap();
+        assert!(feedback.message.contains("not a valid devjournal file"));
+    }
+
+    #[test]
+    fn unpinned_tasks_stay_below_pinned_ones() {
+        let mut state = with_tasks(3);
+        let descs = |state: &mut App| -> Vec<String> {
+            let subproject = state.journal.project().unwrap().subproject().unwrap();
+            subproject.tasks.iter().map(|t| t.desc.clone()).collect()
+        };
+        press(&mut state, KeyCode::Char('j'), KeyModifiers::NONE);
+        press(&mut state, KeyCode::Char('p'), KeyModifiers::NONE);
+        assert_eq!(descs(&mut state), ["Task 1", "Task 0", "Task 2"]);
+        press(&mut state, KeyCode::Char('n'), KeyModifiers::NONE);
+        answer_prompt(&mut state, "Added");
+        assert_eq!(descs(&mut state)[0], "Task 1");
+        for (code, modifiers) in [
+            (KeyCode::Up, KeyModifiers::CONTROL),
+            (KeyCode::Home, KeyModifiers::CONTROL),
+        ] {
+            press(&mut state, KeyCode::Char('G'), KeyModifiers::SHIFT);
+            press(&mut state, code, modifiers);
+            press(&mut state, code, modifiers);
+            let tasks = descs(&mut state);
+            assert_eq!(tasks[0], "Task 1");
+            assert_eq!(selected_task(&mut state).desc, tasks[1]);
+        }
+        // Pinning another moves it just below the pinned task
+        press(&mut state, KeyCode::Char('G'), KeyModifiers::SHIFT);
+        press(&mut state, KeyCode::Char('p'), KeyModifiers::NONE);
+        let tasks = descs(&mut state);
+        assert_eq!(tasks[0], "Task 1");
+        assert_eq!(selected_task(&mut state).desc, tasks[1]);
+        press(&mut state, KeyCode::Char('p'), KeyModifiers::NONE);
+        let tasks = descs(&mut state);
+        assert_eq!(selected_task(&mut state).desc, tasks[1]);
+    }
+
+    #[test]
+    fn clears_password_prompts_when_resolved() {
+        let mut state = App::new(PathBuf::new());
+        press(&mut state, KeyCode::Char('p'), KeyModifiers::CONTROL);
+        answer_prompt(&mut state, "secret");
+        let project = state.journal.project().unwrap();
+        assert!(matches!(
+            &project.prompt_request,
+            Some(JournalPrompt::ConfirmPassword(password)) if **password == *"secret"
+        ));
+        assert_eq!(project.prompt.get_text(), "");
+        project.prompt.set_text("typo");
+        press(&mut state, KeyCode::Esc, KeyModifiers::NONE);
+        let project = state.journal.project().unwrap();
+        assert!(project.prompt_request.is_none());
+        assert_eq!(project.prompt.get_text(), "");
+        assert!(state.journal.password.is_empty());
     }
 }
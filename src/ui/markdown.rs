@@ -0,0 +1,146 @@
+// Minimal Markdown rendering: bold, italics, code spans and bullet lists
+use tui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+};
+
+const BULLET: &str = "• ";
+
+/// Render each line of the text, emphasis does not carry over between lines
+pub fn to_spans(text: &str, style: Style, code_style: Style) -> Vec<Spans<'static>> {
+    text.lines()
+        .map(|line| {
+            let indent = line.len() - line.trim_start().len();
+            let trimmed = line.trim_start();
+            let mut spans = Vec::new();
+            let content = match trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+            {
+                Some(item) => {
+                    spans.push(Span::styled(format!("{}{BULLET}", &line[..indent]), style));
+                    item
+                }
+                None => line,
+            };
+            spans.extend(inline_spans(content, style, code_style));
+            Spans::from(spans)
+        })
+        .collect()
+}
+
+fn inline_spans(line: &str, style: Style, code_style: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let (mut bold, mut italic, mut code) = (false, false, false);
+    let mut chars = line.chars().peekable();
+    let mut prev = None;
+    while let Some(c) = chars.next() {
+        let is_marker = match c {
+            '`' => true,
+            '*' => !code,
+            // Not within a word, as in snake_case
+            '_' => {
+                let alphanumeric = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric());
+                let in_word = alphanumeric(prev.as_ref()) && alphanumeric(chars.peek());
+                !code && !in_word
+            }
+            _ => false,
+        };
+        prev = Some(c);
+        if !is_marker {
+            text.push(c);
+            continue;
+        }
+        if !text.is_empty() {
+            let current = current_style(style, code_style, bold, italic, code);
+            spans.push(Span::styled(std::mem::take(&mut text), current));
+        }
+        match c {
+            '`' => code = !code,
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                bold = !bold;
+            }
+            _ => italic = !italic,
+        }
+    }
+    if !text.is_empty() {
+        spans.push(Span::styled(
+            text,
+            current_style(style, code_style, bold, italic, code),
+        ));
+    }
+    spans
+}
+
+fn current_style(style: Style, code_style: Style, bold: bool, italic: bool, code: bool) -> Style {
+    if code {
+        return code_style;
+    }
+    let mut current = style;
+    if bold {
+        current = current.add_modifier(Modifier::BOLD);
+    }
+    if italic {
+        current = current.add_modifier(Modifier::ITALIC);
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color;
+
+    fn render(text: &str) -> Vec<Vec<(String, Style)>> {
+        let code = Style::default().fg(Color::Yellow);
+        to_spans(text, Style::default(), code)
+            .into_iter()
+            .map(|spans| {
+                spans
+                    .0
+                    .into_iter()
+                    .map(|span| (span.content.into_owned(), span.style))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn emphasis_and_code_become_styled_spans() {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let italic = plain.add_modifier(Modifier::ITALIC);
+        let code = plain.fg(Color::Yellow);
+        assert_eq!(
+            render("a **b** _c_ `*d*`"),
+            vec![vec![
+                ("a ".to_owned(), plain),
+                ("b".to_owned(), bold),
+                (" ".to_owned(), plain),
+                ("c".to_owned(), italic),
+                (" ".to_owned(), plain),
+                ("*d*".to_owned(), code),
+            ]]
+        );
+    }
+
+    #[test]
+    fn bullets_keep_indent_and_snake_case_is_literal() {
+        let plain = Style::default();
+        assert_eq!(
+            render("  - snake_case\n* item"),
+            vec![
+                vec![("  • ".to_owned(), plain), ("snake_case".to_owned(), plain)],
+                vec![("• ".to_owned(), plain), ("item".to_owned(), plain)],
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_does_not_carry_over_lines() {
+        let lines = render("**open\nnext");
+        assert_eq!(lines[1], vec![("next".to_owned(), Style::default())]);
+    }
+}
@@ -1,5 +1,18 @@
 use tui::style::{Color, Modifier, Style};
 
+/// Color of a task color label
+pub fn label_color(name: &str) -> Option<Color> {
+    match name {
+        "red" => Some(Color::Red),
+        "yellow" => Some(Color::Yellow),
+        "green" => Some(Color::Green),
+        "cyan" => Some(Color::Cyan),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        _ => None,
+    }
+}
+
 // Layout
 pub fn title() -> Style {
     Style::default()
@@ -36,16 +49,35 @@ pub fn text_good() -> Style {
         .bg(Color::Rgb(0, 48, 48))
 }
 
+pub fn text_caution() -> Style {
+    Style::default()
+        .fg(Color::Rgb(255, 192, 0))
+        .bg(Color::Rgb(28, 28, 0))
+}
+
 pub fn text_warning() -> Style {
     Style::default()
         .fg(Color::Rgb(255, 32, 0))
         .bg(Color::Rgb(28, 28, 0))
 }
 
+pub fn text_critical() -> Style {
+    Style::default()
+        .fg(Color::White)
+        .bg(Color::Rgb(160, 0, 0))
+        .add_modifier(Modifier::BOLD)
+}
+
 pub fn list_text() -> Style {
     Style::default().fg(Color::Rgb(128, 192, 255))
 }
 
+pub fn list_code() -> Style {
+    Style::default()
+        .fg(Color::Rgb(255, 192, 128))
+        .bg(Color::Rgb(24, 24, 32))
+}
+
 pub fn list_text_dim() -> Style {
     Style::default().fg(Color::Rgb(64, 96, 128))
 }
@@ -57,6 +89,10 @@ pub fn list_text_highlight() -> Style {
         .add_modifier(Modifier::BOLD)
 }
 
+pub fn list_marked() -> Style {
+    Style::default().add_modifier(Modifier::UNDERLINED)
+}
+
 // Prompt
 pub fn prompt() -> Style {
     Style::default().fg(Color::Rgb(255, 128, 0))
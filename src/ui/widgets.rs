@@ -1,4 +1,7 @@
 use tui::layout::Rect;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+pub mod choice;
+pub mod confirm;
 pub mod files;
 pub mod list;
 pub mod prompt;
@@ -17,3 +20,24 @@ pub fn center_rect(width: u16, height: u16, chunk: Rect, margin: u16) -> Rect {
         height.min(chunk.height.saturating_sub(margin * 2)),
     )
 }
+
+/// Truncate text to fit a display width (in terminal cells), ending with an ellipsis if cut
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_owned();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        used += char_width;
+        truncated.push(c);
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
@@ -0,0 +1,127 @@
+use super::{center_rect, list::ListWidget};
+use crate::{
+    app::{list::SelectionList, settings::Settings},
+    ui::styles,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::Span,
+    widgets::{Block, Borders, Clear},
+    Frame,
+};
+
+pub enum ChoiceEvent {
+    AwaitingResult,
+    Result(usize),
+    Cancelled,
+}
+
+/// Pick one of several options, by navigating or by number
+#[derive(Default)]
+pub struct ChoiceWidget {
+    title: String,
+    options: SelectionList<String>,
+}
+
+impl ChoiceWidget {
+    pub fn set_options(&mut self, title: &str, labels: &[&str], selected: usize) {
+        self.title = title.to_owned();
+        self.options = SelectionList::from(
+            labels
+                .iter()
+                .enumerate()
+                .map(|(i, label)| format!("{} {label}", i + 1))
+                .collect::<Vec<String>>(),
+        );
+        self.options.select(selected).ok();
+    }
+
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, chunk: Rect, settings: &Settings) {
+        let area = center_rect(30, self.options.len() as u16 + 2, chunk, 1);
+        let list = ListWidget::new(self.options.as_strings(), self.options.selection())
+            .block(
+                Block::default()
+                    .title(Span::styled(&self.title, styles::title()))
+                    .borders(Borders::ALL)
+                    .border_style(styles::border_highlighted()),
+            )
+            .bullets(settings.bullets());
+        f.render_widget(Clear, area);
+        f.render_widget(list, area);
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent) -> ChoiceEvent {
+        match key.code {
+            KeyCode::Esc => ChoiceEvent::Cancelled,
+            KeyCode::Enter => match self.options.selection() {
+                Some(index) => ChoiceEvent::Result(index),
+                None => ChoiceEvent::AwaitingResult,
+            },
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.options.select_next();
+                ChoiceEvent::AwaitingResult
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.options.select_prev();
+                ChoiceEvent::AwaitingResult
+            }
+            KeyCode::Char(c) => match c.to_digit(10).and_then(|d| (d as usize).checked_sub(1)) {
+                Some(index) if index < self.options.len() => ChoiceEvent::Result(index),
+                _ => ChoiceEvent::AwaitingResult,
+            },
+            _ => ChoiceEvent::AwaitingResult,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(widget: &mut ChoiceWidget, code: KeyCode) -> ChoiceEvent {
+        widget.handle_event(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn choice() -> ChoiceWidget {
+        let mut widget = ChoiceWidget::default();
+        widget.set_options("Priority", &["none", "low", "medium", "high"], 1);
+        widget
+    }
+
+    #[test]
+    fn navigates_and_chooses() {
+        let mut widget = choice();
+        assert_eq!(widget.options.as_strings()[1], "2 low");
+        press(&mut widget, KeyCode::Down);
+        press(&mut widget, KeyCode::Char('j'));
+        press(&mut widget, KeyCode::Char('k'));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Enter),
+            ChoiceEvent::Result(2)
+        ));
+    }
+
+    #[test]
+    fn chooses_by_number_or_cancels() {
+        let mut widget = choice();
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('4')),
+            ChoiceEvent::Result(3)
+        ));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('5')),
+            ChoiceEvent::AwaitingResult
+        ));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('0')),
+            ChoiceEvent::AwaitingResult
+        ));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Esc),
+            ChoiceEvent::Cancelled
+        ));
+    }
+}
@@ -0,0 +1,136 @@
+use super::{center_rect, truncate_to_width};
+use crate::{app::settings::Settings, ui::styles};
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub enum ConfirmEvent {
+    AwaitingResult,
+    Yes,
+    No,
+    Cancelled,
+}
+
+/// Ask a yes or no question, defaulting to no
+#[derive(Default)]
+pub struct ConfirmWidget {
+    message: String,
+    yes: bool,
+}
+
+impl ConfirmWidget {
+    pub fn set_message(&mut self, message: &str) {
+        self.message = message.to_owned();
+        self.yes = false;
+    }
+
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, chunk: Rect) {
+        let area = center_rect(50, 5, chunk, 1);
+        let button = |label: &'static str, highlighted: bool| match highlighted {
+            true => Span::styled(label, styles::list_text_highlight()),
+            false => Span::styled(label, styles::text_dim()),
+        };
+        let lines = vec![
+            Spans::from(truncate_to_width(
+                &self.message,
+                area.width.saturating_sub(2) as usize,
+            )),
+            Spans::default(),
+            Spans::from(vec![
+                button(" Yes ", self.yes),
+                Span::raw("   "),
+                button(" No ", !self.yes),
+            ]),
+        ];
+        let paragraph = Paragraph::new(lines)
+            .style(styles::text())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(styles::border_highlighted()),
+            );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    /// Answer with the keys configured in the settings, which take precedence over the others
+    pub fn handle_event(&mut self, key: KeyEvent, settings: &Settings) -> ConfirmEvent {
+        match settings.confirm_answer(key.code) {
+            Some(true) => return ConfirmEvent::Yes,
+            Some(false) => return ConfirmEvent::No,
+            None => (),
+        }
+        match key.code {
+            KeyCode::Esc => ConfirmEvent::Cancelled,
+            KeyCode::Enter => match self.yes {
+                true => ConfirmEvent::Yes,
+                false => ConfirmEvent::No,
+            },
+            KeyCode::Left | KeyCode::Right | KeyCode::Tab | KeyCode::Char('h' | 'l') => {
+                self.yes = !self.yes;
+                ConfirmEvent::AwaitingResult
+            }
+            _ => ConfirmEvent::AwaitingResult,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(widget: &mut ConfirmWidget, code: KeyCode, settings: &Settings) -> ConfirmEvent {
+        widget.handle_event(KeyEvent::new(code, KeyModifiers::NONE), settings)
+    }
+
+    #[test]
+    fn enter_answers_the_highlighted_button() {
+        let settings = Settings::default();
+        let mut widget = ConfirmWidget::default();
+        widget.set_message("Delete?");
+        assert!(matches!(
+            press(&mut widget, KeyCode::Enter, &settings),
+            ConfirmEvent::No
+        ));
+        press(&mut widget, KeyCode::Tab, &settings);
+        assert!(matches!(
+            press(&mut widget, KeyCode::Enter, &settings),
+            ConfirmEvent::Yes
+        ));
+        // A new question starts on No again
+        widget.set_message("Really?");
+        assert!(matches!(
+            press(&mut widget, KeyCode::Enter, &settings),
+            ConfirmEvent::No
+        ));
+    }
+
+    #[test]
+    fn answers_with_keys() {
+        let settings = Settings::default();
+        let mut widget = ConfirmWidget::default();
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('y'), &settings),
+            ConfirmEvent::Yes
+        ));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('N'), &settings),
+            ConfirmEvent::No
+        ));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Esc, &settings),
+            ConfirmEvent::Cancelled
+        ));
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('x'), &settings),
+            ConfirmEvent::AwaitingResult
+        ));
+    }
+}
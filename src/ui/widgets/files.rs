@@ -1,26 +1,183 @@
-use super::{list::ListWidget, prompt::PromptWidget};
-use crate::{app::list::SelectionList, ui::styles};
+use super::{
+    list::{scroll_offset, ListWidget},
+    prompt::PromptWidget,
+    truncate_to_width,
+};
+use crate::{
+    app::{
+        data::DATE_FORMAT,
+        export::{EXPORTS_DIR, MIRROR_SUFFIX},
+        list::SelectionList,
+        settings::{Settings, SETTINGS_BACKUP_FILENAME, SETTINGS_FILENAME},
+    },
+    ui::styles,
+};
+use chrono::{DateTime, Local};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::{
-    fs::{self, read_dir, remove_file},
-    path::PathBuf,
+    cell::Cell,
+    cmp::Reverse,
+    fs::{self, read_dir, Metadata},
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
 };
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::Style,
     text::Span,
-    widgets::{Block, Borders, Clear},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
+
+/// Subfolder of the data directory that deleted files are moved to
+const TRASH_DIR: &str = "trash";
+/// Subfolder of the data directory for journals to start new journals from
+pub const TEMPLATES_DIR: &str = "templates";
+/// Subfolder of the data directory for checkpoints, in a folder per journal file
+pub const CHECKPOINTS_DIR: &str = "checkpoints";
+const TRASH_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Move a file to the trash, prefixed with the time of deletion
+fn trash_file(datadir: &Path, name: &str) -> io::Result<()> {
+    let trash = datadir.join(TRASH_DIR);
+    fs::create_dir_all(&trash)?;
+    let timestamp = Local::now().format(TRASH_TIMESTAMP_FORMAT);
+    fs::rename(
+        datadir.join(name),
+        trash.join(format!("{timestamp}_{name}")),
+    )
+}
+
+/// Move a trashed file back to the data directory, returning its restored name
+fn restore_file(datadir: &Path, trashed_name: &str) -> io::Result<String> {
+    let name = trashed_name
+        .split_once('_')
+        .map_or(trashed_name, |(_, name)| name);
+    let mut restored_name = name.to_owned();
+    let mut suffix = 2;
+    while datadir.join(&restored_name).exists() {
+        restored_name = format!("{name} ({suffix})");
+        suffix += 1;
+    }
+    fs::rename(
+        datadir.join(TRASH_DIR).join(trashed_name),
+        datadir.join(&restored_name),
+    )?;
+    Ok(restored_name)
+}
+
+/// Permanently delete all trashed files, returning how many were deleted
+fn empty_trash(datadir: &Path) -> io::Result<usize> {
+    let trash = datadir.join(TRASH_DIR);
+    if !trash.exists() {
+        return Ok(0);
+    }
+    let count = read_dir(&trash)?.count();
+    fs::remove_dir_all(trash)?;
+    Ok(count)
+}
+
+/// Size and modification date, with placeholders for what cannot be read
+fn file_details(metadata: Option<&Metadata>) -> String {
+    let size = metadata.map_or_else(|| "-".to_owned(), |m| format_size(m.len()));
+    let modified = metadata.and_then(|m| m.modified().ok()).map_or_else(
+        || "-".to_owned(),
+        |time| {
+            DateTime::<Local>::from(time)
+                .format(DATE_FORMAT)
+                .to_string()
+        },
+    );
+    format!("{size:>9}  {modified:>10}")
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Names and details of the files in the directory, most recently modified first
+fn list_files(dir: &Path) -> Vec<(String, String)> {
+    let Ok(dir_entries) = read_dir(dir) else {
+        // Subfolders do not exist until a file is put in them
+        return Vec::new();
+    };
+    let mut entries: Vec<(PathBuf, Option<Metadata>)> = dir_entries
+        .filter_map(|res| res.ok().map(|entry| entry.path()))
+        .filter(|x| {
+            x.is_file()
+                && !x.ends_with(".config")
+                && !x.ends_with(SETTINGS_FILENAME)
+                && !x.ends_with(SETTINGS_BACKUP_FILENAME)
+                && !x.to_string_lossy().ends_with(MIRROR_SUFFIX)
+        })
+        .map(|file| {
+            let metadata = fs::metadata(&file).ok();
+            (file, metadata)
+        })
+        .collect();
+    // Most recently modified first, files without a modification time last
+    entries.sort_by_key(|(_, metadata)| Reverse(metadata.as_ref().and_then(|m| m.modified().ok())));
+    entries
+        .into_iter()
+        .filter_map(|(file, metadata)| {
+            let name = file.file_name()?.to_string_lossy().to_string();
+            Some((name, file_details(metadata.as_ref())))
+        })
+        .collect()
+}
+
+/// Index a page of rows away from the selected index, stopping at the first or last index
+fn page_index(selected: Option<usize>, len: usize, rows: usize, down: bool) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    let page = rows.max(1);
+    Some(match (selected, down) {
+        (None, _) => 0,
+        (Some(index), true) => index.saturating_add(page).min(last),
+        (Some(index), false) => index.saturating_sub(page).min(last),
+    })
+}
+
+/// The name followed by the details, aligned to the right of the width
+fn format_entry(name: &str, details: &str, width: usize) -> String {
+    let name_width = width.saturating_sub(details.width() + 1);
+    let name = truncate_to_width(name, name_width);
+    let padding = name_width.saturating_sub(name.width()) + 1;
+    format!("{name}{}{details}", " ".repeat(padding))
+}
 
 pub enum FileListResult {
     AwaitingResult,
     Feedback(String),
+    /// Asks to permanently delete the number of files in the trash, see `empty_trash`
+    EmptyTrash(usize),
     Result(String),
     Cancelled,
 }
 
+#[derive(Clone, PartialEq, Eq)]
+enum Listing {
+    Journals,
+    Trash,
+    Templates,
+    Exports,
+    /// Checkpoints of the named journal file
+    Checkpoints(String),
+}
+
 enum Focus {
     FileList,
     Prompt,
@@ -30,8 +187,18 @@ pub struct FileListWidget<'a> {
     prompt: PromptWidget<'a>,
     datadir: String,
     filelist: SelectionList<String>,
+    /// Size and modification date of each file
+    details: Vec<String>,
     focus: Focus,
     title: String,
+    /// Which folder is being listed
+    listing: Listing,
+    /// Which folder the current entries are of, kept while refreshing
+    listed: Option<Listing>,
+    /// Entries being read in the background, see `poll`
+    pending: Option<Receiver<Vec<(String, String)>>>,
+    /// Rows of entries when last drawn, the distance of paging
+    page_rows: Cell<usize>,
     style_title: Style,
     style_border: Style,
 }
@@ -42,13 +209,17 @@ impl<'a> FileListWidget<'a> {
             prompt: PromptWidget::default().focus(false).margin(0),
             datadir: datadir.to_owned(),
             filelist: SelectionList::default(),
+            details: Vec::new(),
             focus: Focus::FileList,
             title: "Files".to_owned(),
+            listing: Listing::Journals,
+            listed: None,
+            pending: None,
+            page_rows: Cell::new(1),
             style_title: styles::title(),
             style_border: styles::border_highlighted(),
         };
         widget.reset();
-        widget.filelist.select_next();
         widget
     }
 
@@ -57,32 +228,93 @@ impl<'a> FileListWidget<'a> {
     }
 
     pub fn reset(&mut self) {
+        self.listing = Listing::Journals;
         self.set_focus(Focus::FileList);
         self.refresh_filelist();
     }
 
+    /// Reset to list the templates folder
+    pub fn reset_templates(&mut self) {
+        self.reset();
+        self.listing = Listing::Templates;
+        self.refresh_filelist();
+    }
+
+    /// Reset to list the checkpoints of a journal file
+    pub fn reset_checkpoints(&mut self, journal_file: &str) {
+        self.reset();
+        self.listing = Listing::Checkpoints(journal_file.to_owned());
+        self.refresh_filelist();
+    }
+
+    /// Reset to list the exports folder
+    pub fn reset_exports(&mut self) {
+        self.reset();
+        self.listing = Listing::Exports;
+        self.refresh_filelist();
+    }
+
     fn refresh_filelist(&mut self) {
-        let mut entries: Vec<PathBuf> = read_dir(&self.datadir)
-            .expect("cannot read directory")
-            .map(|res| res.expect("cannot read file").path())
-            .filter(|x| x.is_file() && !x.ends_with(".config"))
-            .collect();
-        entries.sort_by_key(|file| {
-            fs::metadata(file)
-                .expect("cannot read metadata")
-                .modified()
-                .expect("cannot read system time")
-                .elapsed()
-                .expect("cannot file age")
-        });
+        let dir = match &self.listing {
+            Listing::Journals => PathBuf::from(&self.datadir),
+            Listing::Trash => Path::new(&self.datadir).join(TRASH_DIR),
+            Listing::Templates => Path::new(&self.datadir).join(TEMPLATES_DIR),
+            Listing::Exports => Path::new(&self.datadir).join(EXPORTS_DIR),
+            Listing::Checkpoints(journal_file) => Path::new(&self.datadir)
+                .join(CHECKPOINTS_DIR)
+                .join(journal_file),
+        };
+        // Entries of another folder would be misleading while waiting
+        if self.listed.as_ref() != Some(&self.listing) {
+            self.filelist.clear_items();
+            self.filelist.deselect();
+            self.details.clear();
+        }
+        // Reading a large or slow folder must not stall drawing and input
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || sender.send(list_files(&dir)));
+        self.pending = Some(receiver);
+    }
+
+    /// Show the entries once read, keeping the selected file selected
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.pending else {
+            return;
+        };
+        let entries = match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Vec::new(),
+            Ok(entries) => entries,
+        };
+        self.pending = None;
+        self.listed = Some(self.listing.clone());
+        let selected = self.filelist.selected().cloned();
         self.filelist.clear_items();
-        for file in entries {
-            self.filelist.push_item(
-                file.file_name()
-                    .expect("cannot get file name")
-                    .to_string_lossy()
-                    .to_string(),
-            );
+        self.details.clear();
+        for (name, details) in entries {
+            self.filelist.push_item(name);
+            self.details.push(details);
+        }
+        let index = selected
+            .and_then(|name| self.filelist.iter().position(|n| *n == name))
+            .or(self.filelist.selection())
+            .unwrap_or_default();
+        match self.filelist.is_empty() {
+            true => self.filelist.deselect(),
+            false => {
+                let last = self.filelist.len() - 1;
+                self.filelist.select(index.min(last)).ok();
+            }
+        }
+    }
+
+    /// Permanently delete the files in the trash, once confirmed, returning feedback
+    pub fn empty_trash(&mut self) -> String {
+        let result = empty_trash(Path::new(&self.datadir));
+        self.refresh_filelist();
+        match result {
+            Ok(count) => format!("Emptied trash ({count} files)"),
+            Err(e) => format!("Failed to empty trash: {e}"),
         }
     }
 
@@ -90,7 +322,7 @@ impl<'a> FileListWidget<'a> {
         self.prompt.set_prompt_text(text);
     }
 
-    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, chunk: Rect) {
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, chunk: Rect, settings: &Settings) {
         f.render_widget(Clear, chunk);
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -99,16 +331,58 @@ impl<'a> FileListWidget<'a> {
                 Constraint::Length(3),
             ])
             .split(chunk);
-        let file_list = ListWidget::new(self.filelist.as_strings(), self.filelist.selection())
+        // Within the borders and after the bullet
+        let width = chunks[0].width.saturating_sub(4) as usize;
+        let rows = chunks[0].height.saturating_sub(2);
+        self.page_rows.set(rows as usize);
+        let entries = self
+            .filelist
+            .iter()
+            .zip(&self.details)
+            .map(|(name, details)| format_entry(name, details, width))
+            .collect();
+        let file_list = ListWidget::new(entries, self.filelist.selection())
             .block(
                 Block::default()
-                    .title(Span::styled(&self.title, self.style_title))
+                    .title(Span::styled(
+                        format!(
+                            "{}{}",
+                            match self.listing {
+                                Listing::Trash => "Trash (Enter to restore, D to empty):",
+                                _ => &self.title,
+                            },
+                            match self.pending {
+                                Some(_) => " refreshing…",
+                                None => "",
+                            }
+                        ),
+                        self.style_title,
+                    ))
                     .borders(Borders::ALL)
                     .border_style(self.style_border),
             )
+            .bullets(settings.bullets())
             .focus(matches!(&self.focus, Focus::FileList));
         f.render_widget(file_list, chunks[0]);
-        self.prompt.draw(f, chunks[1]);
+        if !self.filelist.is_empty() && chunks[0].width > 4 {
+            let list_area = Rect::new(chunks[0].x, chunks[0].y, chunks[0].width, rows);
+            let first = scroll_offset(list_area, self.filelist.selection());
+            let last = (first + rows as usize).min(self.filelist.len());
+            let count = Paragraph::new(Span::styled(
+                format!(" {}–{last} of {} ", first + 1, self.filelist.len()),
+                self.style_title,
+            ))
+            .alignment(Alignment::Right);
+            let border = Rect::new(chunks[0].x + 1, chunks[0].y, chunks[0].width - 2, 1);
+            f.render_widget(count, border);
+        }
+        self.prompt.draw(f, chunks[1], settings);
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        if let Focus::Prompt = self.focus {
+            self.prompt.paste(text);
+        }
     }
 
     pub fn handle_event(&mut self, key: KeyEvent) -> FileListResult {
@@ -153,14 +427,48 @@ impl<'a> FileListWidget<'a> {
             }
             (KeyCode::Down, KeyModifiers::NONE) => self.filelist.select_next(),
             (KeyCode::Up, KeyModifiers::NONE) => self.filelist.select_prev(),
-            (KeyCode::Char('d'), KeyModifiers::NONE) => {
-                if let Some(name) = self.filelist.pop_selected() {
-                    remove_file(PathBuf::from(&self.datadir).join(&name))
-                        .expect("failed to remove file");
+            (KeyCode::PageDown | KeyCode::PageUp, KeyModifiers::NONE) => {
+                let down = key.code == KeyCode::PageDown;
+                let rows = self.page_rows.get();
+                let len = self.filelist.len();
+                if let Some(index) = page_index(self.filelist.selection(), len, rows, down) {
+                    self.filelist.select(index).ok();
+                }
+            }
+            (KeyCode::Char('t'), KeyModifiers::NONE)
+                if matches!(self.listing, Listing::Journals | Listing::Trash) =>
+            {
+                self.listing = match self.listing {
+                    Listing::Trash => Listing::Journals,
+                    _ => Listing::Trash,
+                };
+                self.refresh_filelist();
+            }
+            (KeyCode::Enter, KeyModifiers::NONE) if self.listing == Listing::Trash => {
+                if let Some(name) = self.filelist.selected().cloned() {
+                    let result = restore_file(Path::new(&self.datadir), &name);
+                    self.refresh_filelist();
+                    return FileListResult::Feedback(match result {
+                        Ok(restored_name) => format!("Restored from trash: {restored_name}"),
+                        Err(e) => format!("Failed to restore {name}: {e}"),
+                    });
+                }
+            }
+            (KeyCode::Char('d'), KeyModifiers::NONE) if self.listing == Listing::Journals => {
+                if let Some(name) = self.filelist.selected().cloned() {
+                    let result = trash_file(Path::new(&self.datadir), &name);
                     self.reset();
-                    return FileListResult::Feedback(format!("Deleted project file: {name}"));
+                    return FileListResult::Feedback(match result {
+                        Ok(_) => format!("Moved to trash: {name}"),
+                        Err(e) => format!("Failed to move {name} to trash: {e}"),
+                    });
                 }
             }
+            (KeyCode::Char('D'), KeyModifiers::SHIFT)
+                if self.listing == Listing::Trash && !self.filelist.is_empty() =>
+            {
+                return FileListResult::EmptyTrash(self.filelist.len());
+            }
             (KeyCode::Enter, KeyModifiers::NONE) => {
                 if let Some(filename) = self.filelist.selected() {
                     return FileListResult::Result(filename.clone());
@@ -189,3 +497,148 @@ impl<'a> FileListWidget<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    fn press(
+        widget: &mut FileListWidget,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> FileListResult {
+        widget.handle_event(KeyEvent::new(code, modifiers))
+    }
+
+    /// Wait for the background listing
+    fn listed(widget: &mut FileListWidget) {
+        while widget.pending.is_some() {
+            widget.poll();
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn trashes_and_restores_file() {
+        let datadir = scratch_dir("trash_restore");
+        fs::write(datadir.join("journal"), "data").unwrap();
+        let mut widget = FileListWidget::new(&datadir.to_string_lossy());
+        listed(&mut widget);
+        press(&mut widget, KeyCode::Char('d'), KeyModifiers::NONE);
+        assert!(!datadir.join("journal").exists());
+        assert_eq!(read_dir(datadir.join(TRASH_DIR)).unwrap().count(), 1);
+        press(&mut widget, KeyCode::Char('t'), KeyModifiers::NONE);
+        listed(&mut widget);
+        assert!(matches!(
+            press(&mut widget, KeyCode::Enter, KeyModifiers::NONE),
+            FileListResult::Feedback(_)
+        ));
+        assert_eq!(fs::read_to_string(datadir.join("journal")).unwrap(), "data");
+        assert_eq!(read_dir(datadir.join(TRASH_DIR)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn empties_trash_only_from_trash_listing() {
+        let datadir = scratch_dir("empty_trash");
+        fs::write(datadir.join("journal"), "data").unwrap();
+        trash_file(&datadir, "journal").unwrap();
+        let mut widget = FileListWidget::new(&datadir.to_string_lossy());
+        listed(&mut widget);
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('D'), KeyModifiers::SHIFT),
+            FileListResult::AwaitingResult
+        ));
+        press(&mut widget, KeyCode::Char('t'), KeyModifiers::NONE);
+        listed(&mut widget);
+        assert!(matches!(
+            press(&mut widget, KeyCode::Char('D'), KeyModifiers::SHIFT),
+            FileListResult::EmptyTrash(1)
+        ));
+        // Nothing is deleted until confirmed
+        assert_eq!(read_dir(datadir.join(TRASH_DIR)).unwrap().count(), 1);
+        widget.empty_trash();
+        assert!(!datadir.join(TRASH_DIR).exists());
+    }
+
+    #[test]
+    fn lists_trash_and_restores_under_a_free_name() {
+        let datadir = scratch_dir("trash_listing");
+        fs::write(datadir.join("journal"), "old").unwrap();
+        trash_file(&datadir, "journal").unwrap();
+        fs::write(datadir.join("journal"), "new").unwrap();
+        let mut widget = FileListWidget::new(&datadir.to_string_lossy());
+        listed(&mut widget);
+        // The trash folder itself is not listed
+        assert_eq!(widget.filelist.as_strings(), ["journal"]);
+        press(&mut widget, KeyCode::Char('t'), KeyModifiers::NONE);
+        listed(&mut widget);
+        let trashed = widget.filelist.as_strings();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].ends_with("_journal"));
+        assert_eq!(restore_file(&datadir, &trashed[0]).unwrap(), "journal (2)");
+        assert_eq!(fs::read_to_string(datadir.join("journal")).unwrap(), "new");
+        assert_eq!(
+            fs::read_to_string(datadir.join("journal (2)")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn file_details_show_size_and_date() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_size(3 << 40), "3072.0 GiB");
+        let datadir = scratch_dir("file_details");
+        fs::write(datadir.join("journal"), [0; 2048]).unwrap();
+        let metadata = fs::metadata(datadir.join("journal")).unwrap();
+        let today = Local::now().format(DATE_FORMAT).to_string();
+        assert_eq!(
+            file_details(Some(&metadata)),
+            format!("{:>9}  {today:>10}", "2.0 KiB")
+        );
+        assert_eq!(file_details(None), format!("{:>9}  {:>10}", "-", "-"));
+    }
+
+    #[test]
+    fn keeps_cached_entries_while_refreshing() {
+        let datadir = scratch_dir("cached_listing");
+        fs::write(datadir.join("first"), "data").unwrap();
+        let mut widget = FileListWidget::new(&datadir.to_string_lossy());
+        listed(&mut widget);
+        assert_eq!(
+            widget.filelist.selected().map(String::as_str),
+            Some("first")
+        );
+        fs::write(datadir.join("second"), "data").unwrap();
+        widget.reset();
+        // The same folder shows its last entries until the new ones are read
+        assert!(widget.pending.is_some());
+        assert_eq!(widget.filelist.iter().collect::<Vec<_>>(), ["first"]);
+        listed(&mut widget);
+        assert_eq!(widget.filelist.len(), 2);
+        assert_eq!(
+            widget.filelist.selected().map(String::as_str),
+            Some("first")
+        );
+        // Another folder does not
+        widget.reset_templates();
+        assert_eq!(widget.filelist.len(), 0);
+        assert_eq!(widget.filelist.selection(), None);
+    }
+
+    #[test]
+    fn pages_stop_at_the_list_boundaries() {
+        assert_eq!(page_index(Some(0), 340, 12, true), Some(12));
+        assert_eq!(page_index(Some(12), 340, 12, false), Some(0));
+        assert_eq!(page_index(Some(335), 340, 12, true), Some(339));
+        assert_eq!(page_index(Some(339), 340, 12, true), Some(339));
+        assert_eq!(page_index(Some(5), 340, 12, false), Some(0));
+        assert_eq!(page_index(None, 340, 12, true), Some(0));
+        // A list too short to draw still moves by one
+        assert_eq!(page_index(Some(1), 3, 0, true), Some(2));
+        assert_eq!(page_index(Some(0), 0, 12, true), None);
+    }
+}
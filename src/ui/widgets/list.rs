@@ -1,10 +1,19 @@
-use crate::ui::styles;
+use super::truncate_to_width;
+use crate::{
+    app::settings::{BULLETS_UNICODE, BULLET_CHILD_UNICODE},
+    ui::styles,
+};
 use tui::{
     buffer::Buffer,
     layout::Rect,
+    style::{Color, Style},
     text::Spans,
     widgets::{Block, Widget},
 };
+use unicode_width::UnicodeWidthStr;
+
+/// Narrowest an item is left by its suffix, see `ListWidget::suffixes`
+const SUFFIX_MIN_TEXT_WIDTH: usize = 12;
 
 #[derive(Debug, Clone)]
 pub struct ListWidget<'a> {
@@ -18,7 +27,63 @@ pub struct ListWidget<'a> {
     bullet: char,
     /// Bullet point for selected item
     bullet_selected: char,
+    /// Bullet point for nested items
+    bullet_child: char,
+    /// Nesting level of each item
+    depths: Vec<u8>,
+    /// Style of each item, over the default style
+    item_styles: Vec<Option<Style>>,
+    /// Bullet color of each item
+    bullet_colors: Vec<Option<Color>>,
+    /// Text right-aligned at the end of each item, hidden if the item would be left too narrow
+    suffixes: Vec<Option<String>>,
     pub focus: bool,
+    /// Leave the text of the selected item blank, for an editor drawn over it
+    editing: bool,
+}
+
+/// Area of the text (after the bullet) of the selected item within the inner list area
+pub fn selected_row(area: Rect, selected: usize, depth: u8) -> Rect {
+    let row = selected.min(area.height.saturating_sub(1) as usize) as u16;
+    let indent = (indent_width(depth) as u16 + 2).min(area.width);
+    Rect::new(
+        area.x + indent,
+        area.y + row,
+        area.width - indent,
+        1.min(area.height),
+    )
+}
+
+/// Index of the item drawn at row `y` of the inner list area
+pub fn row_at(area: Rect, selected: Option<usize>, len: usize, y: u16) -> Option<usize> {
+    if y < area.top() || y >= area.bottom() {
+        return None;
+    }
+    let index = scroll_offset(area, selected) + (y - area.top()) as usize;
+    (index < len).then_some(index)
+}
+
+/// Scroll just enough to keep the selected item in view
+pub fn scroll_offset(area: Rect, selected: Option<usize>) -> usize {
+    selected.map_or(0, |i| (i + 1).saturating_sub(area.height as usize))
+}
+
+/// The text with the suffix right-aligned to the width, or just the text if the suffix would
+/// leave it less than `SUFFIX_MIN_TEXT_WIDTH`
+fn with_suffix(text: &str, suffix: &str, width: usize) -> String {
+    let Some(text_width) = width
+        .checked_sub(suffix.width() + 1)
+        .filter(|w| *w >= SUFFIX_MIN_TEXT_WIDTH)
+    else {
+        return truncate_to_width(text, width);
+    };
+    let text = truncate_to_width(text, text_width);
+    let padding = width - text.width() - suffix.width();
+    format!("{text}{}{suffix}", " ".repeat(padding))
+}
+
+fn indent_width(depth: u8) -> usize {
+    depth as usize * 2
 }
 
 impl<'a> ListWidget<'a> {
@@ -27,9 +92,15 @@ impl<'a> ListWidget<'a> {
             block: None,
             items,
             selected: highlighted,
-            bullet: '•',
-            bullet_selected: '►',
+            bullet: BULLETS_UNICODE.0,
+            bullet_selected: BULLETS_UNICODE.1,
+            bullet_child: BULLET_CHILD_UNICODE,
+            depths: Vec::new(),
+            item_styles: Vec::new(),
+            bullet_colors: Vec::new(),
+            suffixes: Vec::new(),
             focus: true,
+            editing: false,
         }
     }
 
@@ -38,10 +109,46 @@ impl<'a> ListWidget<'a> {
         self
     }
 
+    pub fn bullets(mut self, (bullet, bullet_selected): (char, char)) -> ListWidget<'a> {
+        self.bullet = bullet;
+        self.bullet_selected = bullet_selected;
+        self
+    }
+
+    pub fn child_bullet(mut self, bullet: char) -> ListWidget<'a> {
+        self.bullet_child = bullet;
+        self
+    }
+
+    pub fn depths(mut self, depths: Vec<u8>) -> ListWidget<'a> {
+        self.depths = depths;
+        self
+    }
+
+    pub fn item_styles(mut self, styles: Vec<Option<Style>>) -> ListWidget<'a> {
+        self.item_styles = styles;
+        self
+    }
+
+    pub fn bullet_colors(mut self, colors: Vec<Option<Color>>) -> ListWidget<'a> {
+        self.bullet_colors = colors;
+        self
+    }
+
+    pub fn suffixes(mut self, suffixes: Vec<Option<String>>) -> ListWidget<'a> {
+        self.suffixes = suffixes;
+        self
+    }
+
     pub fn focus(mut self, focus: bool) -> ListWidget<'a> {
         self.focus = focus;
         self
     }
+
+    pub fn editing(mut self, editing: bool) -> ListWidget<'a> {
+        self.editing = editing;
+        self
+    }
 }
 
 impl<'a> Widget for ListWidget<'a> {
@@ -69,21 +176,111 @@ impl<'a> Widget for ListWidget<'a> {
             false => styles::list_text_dim(),
         };
 
+        let offset = scroll_offset(area, self.selected);
         let x = area.left();
-        let mut y = area.top();
         let width = area.width;
-        for (i, text) in self.items.iter().enumerate() {
-            let mut style = style_normal;
+        let items = self.items.iter().enumerate().skip(offset);
+        for (y, (i, text)) in (area.top()..area.bottom()).zip(items) {
+            let mut style = match self.item_styles.get(i).copied().flatten() {
+                Some(item_style) => style_normal.patch(item_style),
+                None => style_normal,
+            };
             let mut text = text.clone();
+            let depth = self.depths.get(i).copied().unwrap_or_default();
+            let indent = " ".repeat(indent_width(depth));
             if self.selected == Some(i) {
                 style = style_selected;
-                text = format!("{} {}", self.bullet_selected, text);
+                if self.editing {
+                    text.clear();
+                }
+                text = format!("{indent}{} {text}", self.bullet_selected);
+            } else if depth > 0 {
+                text = format!("{indent}{} {text}", self.bullet_child);
             } else {
-                text = format!("{} {}", self.bullet, text);
+                text = format!("{} {text}", self.bullet);
             }
+            let suffix = self.suffixes.get(i).and_then(|s| s.as_deref());
+            let text = match suffix {
+                Some(suffix) if !(self.editing && self.selected == Some(i)) => {
+                    with_suffix(&text, suffix, width as usize)
+                }
+                _ => truncate_to_width(&text, width as usize),
+            };
             buf.set_spans(x, y, &Spans::from(text), width);
             buf.set_style(Rect::new(x, y, width, 1), style);
-            y += 1;
+            let bullet_x = x + indent_width(depth) as u16;
+            if let (Some(color), true) = (
+                self.bullet_colors.get(i).copied().flatten(),
+                bullet_x < area.right(),
+            ) {
+                buf.get_mut(bullet_x, y).set_fg(color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::settings::{Settings, BULLETS_ASCII};
+
+    fn render(widget: ListWidget, width: u16, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+        (0..height)
+            .map(|y| (0..width).map(|x| buf.get(x, y).symbol.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn renders_configured_bullets() {
+        let mut settings = Settings::default();
+        settings.toggle_ascii_bullets();
+        let items = vec!["First".to_owned(), "Second".to_owned()];
+        let widget = ListWidget::new(items, Some(1)).bullets(settings.bullets());
+        assert_eq!(render(widget, 10, 2), ["* First   ", "> Second  "]);
+    }
+
+    #[test]
+    fn truncates_wide_characters_to_the_area() {
+        let items = vec!["漢字漢字漢字".to_owned(), "🦀🦀🦀🦀🦀".to_owned()];
+        let full = Rect::new(0, 0, 12, 2);
+        let mut buf = Buffer::empty(full);
+        ListWidget::new(items, None).render(Rect::new(0, 0, 8, 2), &mut buf);
+        for y in 0..2 {
+            let row: String = (0..8).map(|x| buf.get(x, y).symbol.as_str()).collect();
+            assert!(row.trim_end().ends_with('…'), "{row:?}");
+            assert!((8..12).all(|x| buf.get(x, y).symbol == " "));
         }
+        assert_eq!(truncate_to_width("漢字漢字漢字", 8).width(), 7);
+        assert_eq!(truncate_to_width("🦀🦀", 4), "🦀🦀");
+    }
+
+    #[test]
+    fn indents_nested_items() {
+        let items = vec!["Parent".to_owned(), "Child".to_owned(), "Next".to_owned()];
+        let widget = ListWidget::new(items, Some(2))
+            .bullets(BULLETS_ASCII)
+            .child_bullet('-')
+            .depths(vec![0, 1, 0]);
+        assert_eq!(
+            render(widget, 12, 3),
+            ["* Parent    ", "  - Child   ", "> Next      "]
+        );
+    }
+
+    #[test]
+    fn right_aligns_suffix_unless_too_narrow() {
+        assert_eq!(with_suffix("fix bug", "2d", 20), "fix bug           2d");
+        assert_eq!(
+            with_suffix("a rather long description", "2d", 20),
+            truncate_to_width("a rather long description", 17) + " 2d"
+        );
+        // Too narrow to leave the text `SUFFIX_MIN_TEXT_WIDTH`
+        assert_eq!(with_suffix("fix bug", "2d", 14), "fix bug");
+        let items = vec!["fix bug".to_owned()];
+        let widget = ListWidget::new(items, None).suffixes(vec![Some("2d".to_owned())]);
+        assert!(render(widget, 24, 1)[0].trim_end().ends_with(" 2d"));
     }
 }
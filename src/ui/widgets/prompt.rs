@@ -1,18 +1,19 @@
-use super::center_rect;
-use crate::ui::styles;
-use crossterm::event::{KeyCode, KeyEvent};
+use super::{center_rect, truncate_to_width};
+use crate::{app::settings::Settings, ui::styles};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use tui::{
     backend::Backend,
     layout::Rect,
     style::Style,
-    text::Span,
-    widgets::{Block, Borders, Clear},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 use tui_textarea::{CursorMove, TextArea};
 
 pub enum PromptEvent {
-    AwaitingResult(String),
+    AwaitingResult,
+    Feedback(String),
     Result(String),
     Cancelled,
 }
@@ -29,6 +30,12 @@ pub struct PromptWidget<'a> {
     style_title: Style,
     style_border: Style,
     password: bool,
+    /// Show password text unmasked
+    reveal: bool,
+    /// Maximum number of characters accepted as input
+    max_length: Option<usize>,
+    /// Accept newlines, committing with Ctrl+s instead of Enter
+    multiline: bool,
 }
 
 impl<'a> Default for PromptWidget<'a> {
@@ -43,6 +50,9 @@ impl<'a> Default for PromptWidget<'a> {
             style_title: styles::title(),
             style_border: styles::border_highlighted(),
             password: false,
+            reveal: false,
+            max_length: None,
+            multiline: false,
         };
         widget.set_focus(true);
         widget
@@ -67,15 +77,35 @@ impl<'a> PromptWidget<'a> {
 
     pub fn set_password(&mut self, is_password: bool) {
         self.password = is_password;
+        self.reveal = false;
         self.set_focus(self.focus);
     }
 
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+    }
+
+    pub fn set_multiline(&mut self, multiline: bool) {
+        self.multiline = multiline;
+    }
+
+    /// Characters of all lines, counting line breaks
+    fn text_length(&self) -> usize {
+        let lines = self.textarea.lines();
+        let chars: usize = lines.iter().map(|line| line.chars().count()).sum();
+        chars + lines.len().saturating_sub(1)
+    }
+
+    fn is_masked(&self) -> bool {
+        self.password && !self.reveal
+    }
+
     pub fn set_prompt_text(&mut self, text: &str) {
         self.prompt_text = text.to_owned();
     }
 
     pub fn get_text(&mut self) -> String {
-        self.textarea.lines()[0].to_owned()
+        self.textarea.lines().join("\n")
     }
 
     pub fn set_text(&mut self, text: &str) {
@@ -93,7 +123,7 @@ impl<'a> PromptWidget<'a> {
         if self.focus {
             self.style_title = styles::title();
             self.style_border = styles::border_highlighted();
-            self.textarea.set_cursor_line_style(match self.password {
+            self.textarea.set_cursor_line_style(match self.is_masked() {
                 false => styles::prompt(),
                 true => styles::prompt_password(),
             });
@@ -101,7 +131,7 @@ impl<'a> PromptWidget<'a> {
         } else {
             self.style_title = styles::title_dim();
             self.style_border = styles::border();
-            self.textarea.set_cursor_line_style(match self.password {
+            self.textarea.set_cursor_line_style(match self.is_masked() {
                 false => styles::prompt_dim(),
                 true => styles::prompt_password(),
             });
@@ -111,33 +141,242 @@ impl<'a> PromptWidget<'a> {
 
     pub fn clear(&mut self) {
         self.prompt_text = "".to_owned();
-        self.set_text("");
         self.password = false;
+        self.reveal = false;
+        self.max_length = None;
+        self.multiline = false;
+        // Deleting the text would keep it in the textarea's yank buffer
+        self.textarea = TextArea::default();
+        self.set_focus(self.focus);
     }
 
-    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, chunk: Rect) {
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, chunk: Rect, settings: &Settings) {
         let width = self
             .max_width
             .min((chunk.width as f32 * self.width_hint) as u16);
-        let area = center_rect(width, 3, chunk, self.margin as u16);
+        let height = match self.multiline {
+            true => (self.textarea.lines().len() as u16 + 2).clamp(5, 12),
+            false => 3,
+        };
+        let area = center_rect(width, height, chunk, self.margin as u16);
         f.render_widget(Clear, area);
+        let title = truncate_to_width(&self.prompt_text, area.width.saturating_sub(2) as usize);
         let block = Block::default()
-            .title(Span::styled(&self.prompt_text, self.style_title))
+            .title(Span::styled(title, self.style_title))
             .borders(Borders::ALL)
             .border_style(self.style_border);
         let inner = block.inner(area);
         f.render_widget(block, area);
-        f.render_widget(self.textarea.widget(), inner)
+        match self.is_masked() && settings.password_dots {
+            false => f.render_widget(self.textarea.widget(), inner),
+            true => {
+                let dots = "•".repeat(self.text_length());
+                let spans = Spans::from(vec![
+                    Span::styled(dots, styles::prompt()),
+                    Span::styled(" ", styles::prompt_cursor()),
+                ]);
+                f.render_widget(Paragraph::new(spans), inner);
+            }
+        }
+    }
+
+    /// Draw only the text input, in place of existing text
+    pub fn draw_inline<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        f.render_widget(Clear, area);
+        f.render_widget(self.textarea.widget(), area);
     }
 
     pub fn handle_event(&mut self, key: KeyEvent) -> PromptEvent {
         match key.code {
             KeyCode::Esc => PromptEvent::Cancelled,
-            KeyCode::Enter => PromptEvent::Result(self.get_text()),
+            KeyCode::Enter if !self.multiline => PromptEvent::Result(self.get_text()),
+            KeyCode::Char('s') if self.multiline && key.modifiers == KeyModifiers::CONTROL => {
+                PromptEvent::Result(self.get_text())
+            }
+            KeyCode::F(6) if self.password => {
+                self.reveal = !self.reveal;
+                self.set_focus(self.focus);
+                PromptEvent::AwaitingResult
+            }
+            _ => {
+                let previous_length = self.text_length();
+                self.edit(key);
+                let length = self.text_length();
+                match self.max_length {
+                    // Prefilled text may exceed the limit, but can only shrink
+                    Some(max_length) if length > max_length && length > previous_length => {
+                        self.textarea.undo();
+                        PromptEvent::Feedback(format!("Limited to {max_length} characters"))
+                    }
+                    _ => PromptEvent::AwaitingResult,
+                }
+            }
+        }
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        let room = self
+            .max_length
+            .map_or(usize::MAX, |max| max.saturating_sub(self.text_length()));
+        let text: String = match self.multiline {
+            true => text.lines().collect::<Vec<&str>>().join("\n"),
+            false => text.chars().filter(|c| !matches!(c, '\n' | '\r')).collect(),
+        };
+        let text: String = text.chars().take(room).collect();
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.textarea.insert_newline();
+            }
+            self.textarea.insert_str(line);
+        }
+    }
+
+    fn edit(&mut self, key: KeyEvent) {
+        match (key.code, key.modifiers) {
+            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                self.textarea.move_cursor(CursorMove::Head)
+            }
+            (KeyCode::End, _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
+                self.textarea.move_cursor(CursorMove::End)
+            }
+            (KeyCode::Left, KeyModifiers::CONTROL) | (KeyCode::Char('b'), KeyModifiers::ALT) => {
+                self.textarea.move_cursor(CursorMove::WordBack)
+            }
+            (KeyCode::Right, KeyModifiers::CONTROL) | (KeyCode::Char('f'), KeyModifiers::ALT) => {
+                self.textarea.move_cursor(CursorMove::WordForward)
+            }
+            (KeyCode::Char('w'), KeyModifiers::CONTROL)
+            | (KeyCode::Backspace, KeyModifiers::ALT) => {
+                self.textarea.delete_word();
+            }
+            (KeyCode::Tab, _) => (),
+            // Keys that would break a single-line input
+            (KeyCode::Up | KeyCode::Down, _) | (KeyCode::Char('m'), KeyModifiers::CONTROL)
+                if !self.multiline => {}
             _ => {
                 self.textarea.input(key);
-                PromptEvent::AwaitingResult(self.get_text())
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::{backend::TestBackend, Terminal};
+
+    fn limited(max_length: usize, multiline: bool) -> PromptWidget<'static> {
+        let mut prompt = PromptWidget::default();
+        prompt.set_max_length(Some(max_length));
+        prompt.set_multiline(multiline);
+        prompt
+    }
+
+    #[test]
+    fn paste_is_cut_to_the_remaining_room() {
+        let mut prompt = limited(8, false);
+        prompt.set_text("abc");
+        prompt.paste("defgh\nijk");
+        assert_eq!(prompt.get_text(), "abcdefgh");
+        prompt.paste("more");
+        assert_eq!(prompt.get_text(), "abcdefgh");
+    }
+
+    #[test]
+    fn multiline_paste_counts_every_line() {
+        let mut prompt = limited(8, true);
+        prompt.paste("one\r\ntwo\nthree");
+        assert_eq!(prompt.get_text(), "one\ntwo\n");
+        assert_eq!(prompt.text_length(), 8);
+    }
+
+    #[test]
+    fn typing_stops_at_the_limit_across_lines() {
+        let mut prompt = limited(5, true);
+        prompt.paste("ab\ncd");
+        let event = prompt.handle_event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE));
+        assert!(matches!(event, PromptEvent::Feedback(_)));
+        assert_eq!(prompt.get_text(), "ab\ncd");
+    }
+
+    fn press(prompt: &mut PromptWidget, code: KeyCode, modifiers: KeyModifiers) -> PromptEvent {
+        prompt.handle_event(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn toggles_password_visibility() {
+        let mut prompt = PromptWidget::default();
+        prompt.set_password(true);
+        prompt.set_focus(true);
+        assert_eq!(
+            prompt.textarea.cursor_line_style(),
+            styles::prompt_password()
+        );
+        press(&mut prompt, KeyCode::F(6), KeyModifiers::NONE);
+        assert_eq!(prompt.textarea.cursor_line_style(), styles::prompt());
+        press(&mut prompt, KeyCode::F(6), KeyModifiers::NONE);
+        assert_eq!(
+            prompt.textarea.cursor_line_style(),
+            styles::prompt_password()
+        );
+        // Not a password: nothing to reveal
+        prompt.clear();
+        prompt.set_focus(true);
+        press(&mut prompt, KeyCode::F(6), KeyModifiers::NONE);
+        assert_eq!(prompt.textarea.cursor_line_style(), styles::prompt());
+        assert!(!prompt.reveal);
+    }
+
+    fn count_dots(prompt: &PromptWidget, settings: &Settings) -> usize {
+        let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+        terminal
+            .draw(|frame| prompt.draw(frame, frame.size(), settings))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .iter()
+            .filter(|cell| cell.symbol == "•")
+            .count()
+    }
+
+    #[test]
+    fn password_dots_match_input_length() {
+        let mut prompt = PromptWidget::default();
+        prompt.set_password(true);
+        prompt.set_text("hunter2");
+        let mut settings = Settings::default();
+        assert_eq!(count_dots(&prompt, &settings), 0);
+        settings.password_dots = true;
+        assert_eq!(count_dots(&prompt, &settings), 7);
+    }
+
+    #[test]
+    fn edits_prefilled_text_by_word() {
+        let mut prompt = PromptWidget::default();
+        prompt.set_text("fix the login bug");
+        press(&mut prompt, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut prompt, KeyCode::Char('X'), KeyModifiers::SHIFT);
+        assert_eq!(prompt.get_text(), "Xfix the login bug");
+        press(&mut prompt, KeyCode::End, KeyModifiers::NONE);
+        press(&mut prompt, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut prompt, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(prompt.get_text(), "Xfix the bug");
+        press(&mut prompt, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        press(&mut prompt, KeyCode::Right, KeyModifiers::CONTROL);
+        press(&mut prompt, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(prompt.get_text(), "the bug");
+    }
+
+    #[test]
+    fn clearing_leaves_nothing_to_yank() {
+        let mut prompt = PromptWidget::default();
+        prompt.set_password(true);
+        prompt.set_text("secret");
+        prompt.clear();
+        assert_eq!(prompt.get_text(), "");
+        prompt.textarea.paste();
+        assert_eq!(prompt.get_text(), "");
+        assert!(!prompt.is_masked());
+    }
+}